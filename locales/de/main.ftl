@@ -0,0 +1,52 @@
+## Error responses, keyed by AppError code
+
+error-authentication_failed = Authentifizierung fehlgeschlagen
+error-not_found = Ressource nicht gefunden
+error-database_error = Datenbankfehler
+error-conflict = Die Anfrage steht im Konflikt mit vorhandenen Daten
+error-constraint_violation = Die Anfrage verletzt eine Datenbedingung
+error-service_unavailable = Dienst vorübergehend nicht verfügbar
+error-internal_error = Interner Serverfehler
+error-rate_limit_exceeded = Anfragelimit überschritten
+error-user_already_exists = Benutzer existiert bereits
+error-invalid_credentials = Ungültige Anmeldedaten
+error-invalid_password = Das Passwort erfüllt nicht die Anforderungen
+error-validation_failed = Validierung der Anfrage fehlgeschlagen
+error-email_not_verified = E-Mail-Adresse nicht bestätigt
+error-insufficient_permissions = Unzureichende Berechtigungen
+error-invalid_cursor = Ungültiger Seiten-Cursor
+error-precondition_failed = Die Ressource wurde von einer anderen Anfrage geändert
+error-precondition_required = Der If-Match-Header ist erforderlich
+error-unsupported_media_type = Nicht unterstützter Medientyp
+error-payload_too_large = Anfragetext ist zu groß
+error-request_timeout = Die Anfrage hat zu lange gedauert
+error-invalid_idempotency_key = Idempotency-Key muss aus 1 bis 255 sichtbaren ASCII-Zeichen bestehen
+error-idempotency_key_reused = Idempotency-Key wurde bereits für eine andere Anfrage verwendet
+error-idempotency_key_in_progress = Eine Anfrage mit diesem Idempotency-Key wird noch verarbeitet
+
+## Field validation, keyed by field and validator code
+
+validation-email-email = Ungültiges E-Mail-Format
+validation-password-length = Das Passwort muss zwischen { $min } und { $max } Zeichen lang sein
+validation-password-too_long = Das Passwort darf höchstens { $max } Bytes lang sein; Umlaute und nicht-lateinische Zeichen belegen mehr als ein Byte
+validation-password-uppercase = Das Passwort muss mindestens einen Großbuchstaben enthalten
+validation-password-lowercase = Das Passwort muss mindestens einen Kleinbuchstaben enthalten
+validation-password-digit = Das Passwort muss mindestens eine Ziffer enthalten
+validation-password-special = Das Passwort muss mindestens ein Sonderzeichen enthalten
+validation-password-contains_email = Das Passwort darf Ihre E-Mail-Adresse nicht enthalten
+validation-password-breached = Dieses Passwort ist in einem Datenleck aufgetaucht; wählen Sie ein anderes
+validation-password-reused = Dieses Passwort wurde kürzlich verwendet; wählen Sie ein anderes
+validation-length = Muss mindestens { $min } Zeichen lang sein
+validation-email = Ungültiges E-Mail-Format
+
+## Emails
+
+email-verification-subject = Bestätigen Sie Ihre E-Mail-Adresse
+email-verification-body =
+    Hallo,
+
+    bitte bestätigen Sie Ihre E-Mail-Adresse über den folgenden Link:
+
+    { $link }
+
+    Falls Sie kein Konto erstellt haben, können Sie diese E-Mail ignorieren.
@@ -0,0 +1,52 @@
+## Error responses, keyed by AppError code
+
+error-authentication_failed = Authentication failed
+error-not_found = Resource not found
+error-database_error = Database error
+error-conflict = Request conflicts with existing data
+error-constraint_violation = Request violates a data constraint
+error-service_unavailable = Service temporarily unavailable
+error-internal_error = Internal server error
+error-rate_limit_exceeded = Rate limit exceeded
+error-user_already_exists = User already exists
+error-invalid_credentials = Invalid credentials
+error-invalid_password = Password does not meet requirements
+error-validation_failed = Request validation failed
+error-email_not_verified = Email not verified
+error-insufficient_permissions = Insufficient permissions
+error-invalid_cursor = Invalid pagination cursor
+error-precondition_failed = Resource was modified by another request
+error-precondition_required = If-Match header is required
+error-unsupported_media_type = Unsupported media type
+error-payload_too_large = Request body is too large
+error-request_timeout = Request took too long to process
+error-invalid_idempotency_key = Idempotency-Key must be 1 to 255 visible ASCII characters
+error-idempotency_key_reused = Idempotency-Key was already used for a different request
+error-idempotency_key_in_progress = A request with this Idempotency-Key is still being processed
+
+## Field validation, keyed by field and validator code
+
+validation-email-email = Invalid email format
+validation-password-length = Password must be between { $min } and { $max } characters long
+validation-password-too_long = Password must be at most { $max } bytes long; accented and non-Latin characters take up more than one byte
+validation-password-uppercase = Password must contain at least one uppercase letter
+validation-password-lowercase = Password must contain at least one lowercase letter
+validation-password-digit = Password must contain at least one number
+validation-password-special = Password must contain at least one special character
+validation-password-contains_email = Password must not contain your email address
+validation-password-breached = This password has appeared in a data breach; choose a different one
+validation-password-reused = Password was used recently; choose a different one
+validation-length = Must be at least { $min } characters long
+validation-email = Invalid email format
+
+## Emails
+
+email-verification-subject = Verify your email address
+email-verification-body =
+    Hello,
+
+    Please confirm your email address by opening the link below:
+
+    { $link }
+
+    If you did not create an account, you can ignore this email.
@@ -0,0 +1,52 @@
+## Error responses, keyed by AppError code
+
+error-authentication_failed = Error de autenticación
+error-not_found = Recurso no encontrado
+error-database_error = Error de base de datos
+error-conflict = La solicitud entra en conflicto con datos existentes
+error-constraint_violation = La solicitud infringe una restricción de datos
+error-service_unavailable = Servicio no disponible temporalmente
+error-internal_error = Error interno del servidor
+error-rate_limit_exceeded = Límite de solicitudes excedido
+error-user_already_exists = El usuario ya existe
+error-invalid_credentials = Credenciales no válidas
+error-invalid_password = La contraseña no cumple los requisitos
+error-validation_failed = La validación de la solicitud falló
+error-email_not_verified = Correo electrónico no verificado
+error-insufficient_permissions = Permisos insuficientes
+error-invalid_cursor = Cursor de paginación no válido
+error-precondition_failed = El recurso fue modificado por otra solicitud
+error-precondition_required = Se requiere la cabecera If-Match
+error-unsupported_media_type = Tipo de contenido no admitido
+error-payload_too_large = El cuerpo de la solicitud es demasiado grande
+error-request_timeout = La solicitud tardó demasiado en procesarse
+error-invalid_idempotency_key = Idempotency-Key debe tener entre 1 y 255 caracteres ASCII visibles
+error-idempotency_key_reused = Idempotency-Key ya se usó para una solicitud diferente
+error-idempotency_key_in_progress = Una solicitud con este Idempotency-Key todavía se está procesando
+
+## Field validation, keyed by field and validator code
+
+validation-email-email = Formato de correo electrónico no válido
+validation-password-length = La contraseña debe tener entre { $min } y { $max } caracteres
+validation-password-too_long = La contraseña puede tener como máximo { $max } bytes; los caracteres acentuados y no latinos ocupan más de un byte
+validation-password-uppercase = La contraseña debe contener al menos una letra mayúscula
+validation-password-lowercase = La contraseña debe contener al menos una letra minúscula
+validation-password-digit = La contraseña debe contener al menos un número
+validation-password-special = La contraseña debe contener al menos un carácter especial
+validation-password-contains_email = La contraseña no debe contener tu correo electrónico
+validation-password-breached = Esta contraseña ha aparecido en una filtración de datos; elige otra
+validation-password-reused = Esta contraseña se usó recientemente; elige otra
+validation-length = Debe tener al menos { $min } caracteres
+validation-email = Formato de correo electrónico no válido
+
+## Emails
+
+email-verification-subject = Verifica tu dirección de correo electrónico
+email-verification-body =
+    Hola:
+
+    Confirma tu dirección de correo electrónico abriendo el siguiente enlace:
+
+    { $link }
+
+    Si no creaste una cuenta, puedes ignorar este correo.
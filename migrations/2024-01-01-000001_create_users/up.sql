@@ -1,4 +1,3 @@
--- Up migration
 CREATE TYPE user_role AS ENUM ('admin', 'user');
 
 CREATE TABLE users (
@@ -10,7 +9,3 @@ CREATE TABLE users (
     deleted_at TIMESTAMP,
     created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
 );
-
--- Down migration
-DROP TABLE users;
-DROP TYPE user_role; 
\ No newline at end of file
@@ -0,0 +1,3 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX users_email_trgm_idx ON users USING GIN (email gin_trgm_ops);
@@ -0,0 +1,12 @@
+-- First response per Idempotency-Key when idempotency.backend = "postgres".
+-- `status` is NULL while the first request is still in flight.
+CREATE TABLE idempotency_keys (
+    key TEXT PRIMARY KEY,
+    fingerprint VARCHAR(40) NOT NULL,
+    status SMALLINT,
+    headers TEXT,
+    body BYTEA,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idempotency_keys_expires_at_idx ON idempotency_keys (expires_at);
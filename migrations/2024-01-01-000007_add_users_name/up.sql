@@ -0,0 +1,3 @@
+ALTER TABLE users ADD COLUMN name VARCHAR(255);
+
+CREATE INDEX users_name_trgm_idx ON users USING GIN (name gin_trgm_ops);
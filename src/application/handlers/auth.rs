@@ -3,58 +3,140 @@ use axum::{
     http::StatusCode,
     Json,
 };
-use bcrypt::{verify, hash, DEFAULT_COST};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use crate::{
     infrastructure::{
-        auth::jwt::JwtService,
-        error::AppError,
+        auth::{jwt::JwtService, password::verify_password},
+        error::{AppError, Problem},
+        mail::Mailer,
+        metrics::LOGINS_TOTAL,
+        request_context,
+    },
+    domain::{
+        models::user::UserRole,
+        repositories::{unit_of_work::UnitOfWork, user_repository::UserRepository},
+        services::{password::PasswordService, registration},
     },
-    domain::repositories::user_repository::UserRepository,
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct LoginRequest {
     email: String,
     password: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct LoginResponse {
     token: String,
     user_id: i32,
     email: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct RegisterRequest {
     email: String,
     password: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct RegisterResponse {
     user_id: i32,
     email: String,
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct VerifyEmailRequest {
+    // Token from the link in the verification email
+    token: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ResendVerificationRequest {
+    email: String,
+}
+
+// Verification emails and whether login waits for them
+#[derive(Clone)]
+pub struct EmailVerification {
+    pub mailer: Mailer,
+    pub required: bool,
+}
+
+// Sends in the background in the caller's locale; a failed send is logged
+// and the user can ask for another email
+fn send_verification_email(
+    verification: &EmailVerification,
+    jwt_service: &JwtService,
+    user_id: i32,
+    email: String,
+) {
+    if !verification.mailer.is_enabled() {
+        return;
+    }
+    let token = match jwt_service.generate_email_verification_token(user_id) {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!(user.id = user_id, "Cannot create verification token: {}", e);
+            return;
+        }
+    };
+    let mailer = verification.mailer.clone();
+    let locale = request_context::locale();
+    tokio::spawn(async move {
+        if let Err(e) = mailer.send_verification(&locale, &email, &token).await {
+            tracing::warn!(user.id = user_id, "Verification email not sent: {}", e);
+        }
+    });
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Authenticated", body = LoginResponse),
+        (status = 401, description = "Unknown email or wrong password", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Email address not verified yet", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn login<T: UserRepository>(
     State(repo): State<T>,
     State(jwt_service): State<JwtService>,
+    State(verification): State<EmailVerification>,
     Json(payload): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let result = authenticate(&repo, &jwt_service, verification.required, payload).await;
+    LOGINS_TOTAL
+        .with_label_values(&[if result.is_ok() { "success" } else { "failure" }])
+        .inc();
+    result
+}
+
+async fn authenticate<T: UserRepository>(
+    repo: &T,
+    jwt_service: &JwtService,
+    require_verified_email: bool,
+    payload: LoginRequest,
 ) -> Result<Json<LoginResponse>, AppError> {
     // Find user by email
     let user = repo.find_by_email(&payload.email).await?
-        .ok_or(AppError::AuthenticationError)?;
+        .ok_or(AppError::InvalidCredentials)?;
 
     // Verify password
-    if !verify(payload.password.as_bytes(), &user.password)
-        .map_err(|_| AppError::AuthenticationError)? {
-        return Err(AppError::AuthenticationError);
+    if !verify_password(&payload.password, &user.password)
+        .map_err(|_| AppError::InvalidCredentials)? {
+        return Err(AppError::InvalidCredentials);
+    }
+
+    // Only after the password matched, so this doesn't reveal which emails exist
+    if require_verified_email && !user.is_email_verified {
+        return Err(AppError::EmailNotVerified);
     }
 
     // Generate JWT token
-    let token = jwt_service.generate_token(user.id)?;
+    let token = jwt_service.generate_token(user.id, user.role)?;
 
     Ok(Json(LoginResponse {
         token,
@@ -63,20 +145,82 @@ pub async fn login<T: UserRepository>(
     }))
 }
 
-pub async fn register<T: UserRepository>(
-    State(repo): State<T>,
+#[utoipa::path(
+    post,
+    path = "/auth/register",
+    tag = "auth",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key replay the first response")),
+    request_body = RegisterRequest,
+    responses(
+        (status = 200, description = "Registered", body = RegisterResponse),
+        (status = 409, description = "Email is already registered", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Request validation failed", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn register<U: UnitOfWork>(
+    State(uow): State<U>,
+    State(passwords): State<PasswordService>,
+    State(jwt_service): State<JwtService>,
+    State(verification): State<EmailVerification>,
     Json(payload): Json<RegisterRequest>,
 ) -> Result<Json<RegisterResponse>, AppError> {
-    // Check if user already exists
-    if let Some(_) = repo.find_by_email(&payload.email).await? {
-        return Err(AppError::AuthenticationError);
-    }
-
-    // Create new user
-    let user = repo.create(payload.email, payload.password).await?;
+    let user = registration::register(&uow, &passwords, payload.email, payload.password, UserRole::User).await?;
+    send_verification_email(&verification, &jwt_service, user.id, user.email.clone());
 
     Ok(Json(RegisterResponse {
         user_id: user.id,
         email: user.email,
     }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email",
+    tag = "auth",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 204, description = "Email address verified"),
+        (status = 401, description = "Token is invalid or expired", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn verify_email<T: UserRepository>(
+    State(repo): State<T>,
+    State(jwt_service): State<JwtService>,
+    Json(payload): Json<VerifyEmailRequest>,
+) -> Result<StatusCode, AppError> {
+    let user_id = jwt_service
+        .verify_email_verification_token(&payload.token)
+        .map_err(|_| AppError::AuthenticationError)?;
+    repo.verify_email(user_id).await.map_err(|e| match e {
+        // Deleted since the email was sent
+        AppError::NotFound => AppError::AuthenticationError,
+        e => e,
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/resend-verification",
+    tag = "auth",
+    request_body = ResendVerificationRequest,
+    responses(
+        (status = 202, description = "A new email is sent if the address is registered and not verified yet"),
+    )
+)]
+pub async fn resend_verification<T: UserRepository>(
+    State(repo): State<T>,
+    State(jwt_service): State<JwtService>,
+    State(verification): State<EmailVerification>,
+    Json(payload): Json<ResendVerificationRequest>,
+) -> Result<StatusCode, AppError> {
+    // Same answer whether or not the address is known
+    if let Some(user) = repo.find_by_email(&payload.email).await? {
+        if !user.is_email_verified {
+            send_verification_email(&verification, &jwt_service, user.id, user.email);
+        }
+    }
+
+    Ok(StatusCode::ACCEPTED)
 } 
\ No newline at end of file
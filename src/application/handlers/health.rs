@@ -0,0 +1,63 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use crate::infrastructure::health::{HealthChecker, Readiness, ReadinessSummary};
+
+// The process is up and serving; deliberately checks nothing else so a
+// database outage doesn't get the pod restarted
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses((status = 200, description = "Process is up"))
+)]
+pub async fn live() -> Json<serde_json::Value> {
+    Json(json!({
+        "status": "alive",
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    }))
+}
+
+// 503 while any dependency is down, so the instance is taken out of load
+// balancing until it recovers. Only up/down is exposed publicly; failures
+// are logged, and the per-component report is served on the metrics
+// listener (`readiness_details`).
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "All dependencies are up", body = ReadinessSummary),
+        (status = 503, description = "A dependency is down", body = ReadinessSummary),
+    )
+)]
+pub async fn ready(
+    State(checker): State<HealthChecker>,
+) -> (StatusCode, Json<ReadinessSummary>) {
+    let readiness = checker.readiness().await;
+    for (name, component) in &readiness.components {
+        if let Some(error) = &component.error {
+            tracing::warn!(component = name, "Readiness check failed: {}", error);
+        }
+    }
+    (status_code(&readiness), Json(readiness.summary()))
+}
+
+// Per-component report with errors and pool details; internal only
+pub async fn readiness_details(
+    State(checker): State<HealthChecker>,
+) -> (StatusCode, Json<Readiness>) {
+    let readiness = checker.readiness().await;
+    (status_code(&readiness), Json(readiness))
+}
+
+fn status_code(readiness: &Readiness) -> StatusCode {
+    if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+} 
\ No newline at end of file
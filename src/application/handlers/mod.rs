@@ -1,2 +1,6 @@
 pub mod auth;
-pub mod protected; 
\ No newline at end of file
+pub mod health;
+pub mod protected;
+pub mod user_transfer;
+pub mod users;
+pub mod v2; 
\ No newline at end of file
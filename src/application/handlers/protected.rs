@@ -1,18 +1,33 @@
 use axum::Json;
 use serde::Serialize;
+use utoipa::ToSchema;
 use crate::domain::models::user::Claims;
+use crate::infrastructure::tls::ClientPrincipal;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ProtectedResponse {
     message: String,
     user_id: i32,
+    // Common name of the client certificate, over mutual TLS
+    client_certificate: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/protected",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Token is valid", body = ProtectedResponse),
+    )
+)]
 pub async fn handler(
     claims: axum::extract::Extension<Claims>,
+    principal: Option<ClientPrincipal>,
 ) -> Json<ProtectedResponse> {
     Json(ProtectedResponse {
         message: "This is a protected endpoint".to_string(),
         user_id: claims.sub,
+        client_certificate: principal.map(|principal| principal.common_name),
     })
 } 
\ No newline at end of file
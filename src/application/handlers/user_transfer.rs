@@ -0,0 +1,101 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use crate::{
+    domain::{
+        repositories::user_repository::UserRepository,
+        services::{
+            password::PasswordService,
+            user_transfer::{self, ImportReport, TransferFormat},
+        },
+    },
+    infrastructure::error::{AppError, Problem},
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportUsersQuery {
+    pub format: Option<TransferFormat>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportUsersQuery {
+    pub format: Option<TransferFormat>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/users/import",
+    tag = "admin",
+    params(ImportUsersQuery),
+    request_body(
+        content((String = "text/csv"), (String = "application/x-ndjson")),
+        description = "CSV or newline-delimited JSON, one user per row. Plain-text passwords must meet the password policy; neither they nor `password_hash` values are checked against a user's password history.",
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Per-row import report", body = ImportReport),
+        (status = 403, description = "Caller is not an admin", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "Format could not be determined", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn import_users<T: UserRepository>(
+    State(repo): State<T>,
+    State(passwords): State<PasswordService>,
+    Query(query): Query<ImportUsersQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportReport>, AppError> {
+    // An explicit `format` wins over the request content type
+    let format = query.format
+        .or_else(|| {
+            headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(TransferFormat::from_content_type)
+        })
+        .ok_or(AppError::UnsupportedMediaType)?;
+
+    let report = user_transfer::import_users(&repo, &passwords, format, &body, query.dry_run).await?;
+    Ok(Json(report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/users/export",
+    tag = "admin",
+    params(ExportUsersQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Streamed user export",
+            content((String = "application/x-ndjson"), (String = "text/csv"))),
+        (status = 403, description = "Caller is not an admin", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn export_users<T: UserRepository + Clone>(
+    State(repo): State<T>,
+    Query(query): Query<ExportUsersQuery>,
+) -> impl IntoResponse {
+    let format = query.format.unwrap_or(TransferFormat::Ndjson);
+    let (tx, rx) = mpsc::channel(4);
+
+    tokio::spawn(async move {
+        user_transfer::export_users(&repo, format, tx).await;
+    });
+
+    (
+        [(header::CONTENT_TYPE, format.content_type())],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+} 
\ No newline at end of file
@@ -1,32 +1,88 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{Extension, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::IntoResponse,
     Json,
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use utoipa::{IntoParams, ToSchema};
 use crate::{
-    domain::repositories::user_repository::UserRepository,
-    infrastructure::error::AppError,
+    domain::{
+        models::user::{Claims, User, UserRole},
+        models::user_query::{SortDirection, UserCursor, UserFilter, UserListQuery, UserSortField},
+        models::user_search::{Highlight, UserSearchHit},
+        repositories::{unit_of_work::UnitOfWork, user_repository::UserRepository},
+        services::{password::PasswordService, registration},
+    },
+    infrastructure::error::{AppError, Problem},
 };
 
-#[derive(Deserialize)]
+const DEFAULT_PAGE_SIZE: i64 = 10;
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize, ToSchema)]
 pub struct CreateUserRequest {
     pub email: String,
     pub password: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateUserRequest {
     pub email: Option<String>,
     pub password: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListUsersQuery {
     pub limit: Option<i64>,
-    pub offset: Option<i64>,
+    pub cursor: Option<String>,
+    pub sort: Option<UserSortField>,
+    pub order: Option<SortDirection>,
+    pub role: Option<UserRole>,
+    pub verified: Option<bool>,
+    pub created_after: Option<chrono::NaiveDateTime>,
+    pub created_before: Option<chrono::NaiveDateTime>,
+    pub email: Option<String>,
+    // Admins only
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchUsersQuery {
+    // Matched against email and profile name
+    pub q: String,
+    pub limit: Option<i64>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+pub struct SearchHitResponse {
+    pub user: UserResponse,
+    pub score: f32,
+    pub highlights: Vec<Highlight>,
+}
+
+impl From<UserSearchHit> for SearchHitResponse {
+    fn from(hit: UserSearchHit) -> Self {
+        Self {
+            user: hit.user.into(),
+            score: hit.score,
+            highlights: hit.highlights,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListUsersResponse {
+    pub data: Vec<UserResponse>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct UserResponse {
     pub id: i32,
     pub email: String,
@@ -43,47 +99,329 @@ impl From<crate::domain::models::user::User> for UserResponse {
     }
 }
 
-pub async fn create_user<T: UserRepository>(
-    State(repo): State<T>,
+#[utoipa::path(
+    post,
+    path = "/users",
+    tag = "users",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key replay the first response")),
+    request_body = CreateUserRequest,
+    responses(
+        (status = 200, description = "User created", body = UserResponse),
+        (status = 409, description = "Email is already registered", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Request validation failed", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_user<U: UnitOfWork>(
+    State(uow): State<U>,
+    State(passwords): State<PasswordService>,
     Json(payload): Json<CreateUserRequest>,
 ) -> Result<Json<UserResponse>, AppError> {
-    let user = repo.create(payload.email, payload.password).await?;
+    let user = registration::register(&uow, &passwords, payload.email, payload.password, UserRole::User).await?;
     Ok(Json(user.into()))
 }
 
+#[utoipa::path(
+    get,
+    path = "/users/{id}",
+    tag = "users",
+    params(("id" = i32, Path, description = "User id")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "User, with its version as ETag", body = UserResponse,
+            headers(("ETag" = String, description = "Pass back as If-Match to update"))),
+        (status = 404, description = "No such user", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn get_user<T: UserRepository>(
     State(repo): State<T>,
     Path(id): Path<i32>,
-) -> Result<Json<UserResponse>, AppError> {
+) -> Result<impl IntoResponse, AppError> {
     let user = repo.find_by_id(id).await?
         .ok_or(AppError::NotFound)?;
-    Ok(Json(user.into()))
+    let etag = etag(user.version);
+    Ok(([(header::ETAG, etag)], Json(UserResponse::from(user))))
 }
 
+#[utoipa::path(
+    put,
+    path = "/users/{id}",
+    tag = "users",
+    params(
+        ("id" = i32, Path, description = "User id"),
+        ("If-Match" = String, Header, description = "ETag from a previous read, or `*` for any version"),
+    ),
+    request_body = UpdateUserRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "User updated", body = UserResponse,
+            headers(("ETag" = String, description = "New version"))),
+        (status = 404, description = "No such user", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "User was modified since it was read", body = Problem, content_type = "application/problem+json"),
+        (status = 428, description = "If-Match header is missing", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn update_user<T: UserRepository>(
     State(repo): State<T>,
     Path(id): Path<i32>,
+    headers: HeaderMap,
     Json(payload): Json<UpdateUserRequest>,
-) -> Result<Json<UserResponse>, AppError> {
-    let user = repo.update(id, payload.email, payload.password).await?;
-    Ok(Json(user.into()))
+) -> Result<impl IntoResponse, AppError> {
+    let expected_version = expected_version(&headers)?;
+    let user = repo.update(id, payload.email, payload.password, None, expected_version).await?;
+    let etag = etag(user.version);
+    Ok(([(header::ETAG, etag)], Json(UserResponse::from(user))))
 }
 
+// `None` for `If-Match: *`, which matches whatever version is current
+pub(crate) fn expected_version(headers: &HeaderMap) -> Result<Option<i32>, AppError> {
+    headers
+        .get(header::IF_MATCH)
+        .ok_or(AppError::PreconditionRequired)
+        .and_then(parse_if_match)
+}
+
+pub(crate) fn etag(version: i32) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{}\"", version))
+        .expect("ETag is always a valid header value")
+}
+
+// Accepts a strong `"3"` validator or `*`. If-Match uses strong comparison,
+// so a weak `W/"3"` never matches (RFC 9110, section 13.1.1).
+fn parse_if_match(value: &HeaderValue) -> Result<Option<i32>, AppError> {
+    let value = value
+        .to_str()
+        .map_err(|_| AppError::PreconditionFailed)?
+        .trim();
+
+    if value == "*" {
+        return Ok(None);
+    }
+
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .and_then(|v| v.parse().ok())
+        .map(Some)
+        .ok_or(AppError::PreconditionFailed)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{id}",
+    tag = "users",
+    params(("id" = i32, Path, description = "User id")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "User deleted"),
+        (status = 404, description = "No such user", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn delete_user<T: UserRepository>(
     State(repo): State<T>,
     Path(id): Path<i32>,
 ) -> Result<(), AppError> {
-    repo.delete(id).await?;
+    if !repo.soft_delete(id).await? {
+        return Err(AppError::NotFound);
+    }
     Ok(())
 }
 
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "users",
+    params(ListUsersQuery),
+    responses(
+        (status = 200, description = "One page of users", body = ListUsersResponse),
+        (status = 400, description = "Cursor is malformed or was issued for a different `sort`, `order` or filters", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "`include_deleted` requires an admin token", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn list_users<T: UserRepository>(
     State(repo): State<T>,
+    claims: Option<Extension<Claims>>,
     Query(query): Query<ListUsersQuery>,
-) -> Result<Json<Vec<UserResponse>>, AppError> {
-    let limit = query.limit.unwrap_or(10);
-    let offset = query.offset.unwrap_or(0);
-    
-    let users = repo.list(limit, offset).await?;
-    Ok(Json(users.into_iter().map(Into::into).collect()))
+) -> Result<Json<ListUsersResponse>, AppError> {
+    let (users, next_cursor) = list_page(&repo, claims.as_deref(), query).await?;
+    Ok(Json(ListUsersResponse {
+        data: users.into_iter().map(Into::into).collect(),
+        next_cursor,
+    }))
+}
+
+// One page of users and the cursor of the next one; shared by every API
+// version, which only differ in how users are rendered
+pub(crate) async fn list_page<T: UserRepository>(
+    repo: &T,
+    caller: Option<&Claims>,
+    query: ListUsersQuery,
+) -> Result<(Vec<User>, Option<String>), AppError> {
+    // Soft-deleted accounts are only visible to admins
+    if query.include_deleted && !caller.is_some_and(|claims| claims.role == UserRole::Admin) {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let filter = UserFilter {
+        role: query.role,
+        is_email_verified: query.verified,
+        created_after: query.created_after,
+        created_before: query.created_before,
+        email_contains: query.email.filter(|e| !e.is_empty()),
+        email_or_name_contains: None,
+        include_deleted: query.include_deleted,
+    };
+    let filters = filter_hash(&filter);
+
+    let token = query.cursor.as_deref().map(decode_cursor).transpose()?;
+    if token.as_ref().is_some_and(|token| token.filters != filters) {
+        return Err(AppError::InvalidCursor);
+    }
+
+    // A cursor carries its own sort field and direction; explicit `sort` and
+    // `order` parameters must agree with them
+    let direction = match (&token, query.order) {
+        (Some(token), Some(order)) if token.order != order => {
+            return Err(AppError::InvalidCursor);
+        }
+        (Some(token), _) => token.order,
+        (None, order) => order.unwrap_or(SortDirection::Asc),
+    };
+    let after = token.map(|token| token.after);
+    let sort = match (&after, query.sort) {
+        (Some(cursor), Some(sort)) if cursor.sort_field() != sort => {
+            return Err(AppError::InvalidCursor);
+        }
+        (Some(cursor), _) => cursor.sort_field(),
+        (None, sort) => sort.unwrap_or(UserSortField::CreatedAt),
+    };
+
+    let list_query = UserListQuery {
+        filter,
+        sort,
+        direction,
+        after,
+        // Fetch one extra row to find out whether another page exists
+        limit: limit + 1,
+    };
+
+    let mut users = repo.list(&list_query).await?;
+
+    let next_cursor = if users.len() as i64 > limit {
+        users.truncate(limit as usize);
+        users
+            .last()
+            .map(|user| encode_cursor(&CursorToken {
+                after: UserCursor::for_user(sort, user),
+                order: direction,
+                filters,
+            }))
+            .transpose()?
+    } else {
+        None
+    };
+
+    Ok((users, next_cursor))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/search",
+    tag = "users",
+    params(SearchUsersQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Matches, best first", body = Vec<SearchHitResponse>),
+    )
+)]
+pub async fn search_users<T: UserRepository>(
+    State(repo): State<T>,
+    Query(query): Query<SearchUsersQuery>,
+) -> Result<Json<Vec<SearchHitResponse>>, AppError> {
+    let hits = search(&repo, query).await?;
+    Ok(Json(hits.into_iter().map(Into::into).collect()))
+}
+
+pub(crate) async fn search<T: UserRepository>(
+    repo: &T,
+    query: SearchUsersQuery,
+) -> Result<Vec<UserSearchHit>, AppError> {
+    let term = query.q.trim();
+    if term.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    repo.search(term, limit).await
+}
+
+// A cursor pins the order and filters of the listing it came from, so reusing
+// it with different ones is rejected rather than skipping or repeating rows
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct CursorToken {
+    after: UserCursor,
+    order: SortDirection,
+    filters: String,
+}
+
+fn filter_hash(filter: &UserFilter) -> String {
+    let key = serde_json::json!([
+        filter.role,
+        filter.is_email_verified,
+        filter.created_after,
+        filter.created_before,
+        filter.email_contains,
+        filter.email_or_name_contains,
+        filter.include_deleted,
+    ]);
+    let digest = Sha1::digest(key.to_string().as_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn encode_cursor(cursor: &CursorToken) -> Result<String, AppError> {
+    let json = serde_json::to_vec(cursor).map_err(|_| AppError::InternalServerError)?;
+    Ok(URL_SAFE_NO_PAD.encode(json))
+}
+
+fn decode_cursor(raw: &str) -> Result<CursorToken, AppError> {
+    let json = URL_SAFE_NO_PAD.decode(raw).map_err(|_| AppError::InvalidCursor)?;
+    serde_json::from_slice(&json).map_err(|_| AppError::InvalidCursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(filter: &UserFilter) -> CursorToken {
+        CursorToken {
+            after: UserCursor::Email { email: "a@example.com".to_string(), id: 7 },
+            order: SortDirection::Desc,
+            filters: filter_hash(filter),
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = token(&UserFilter::default());
+        let encoded = encode_cursor(&cursor).unwrap();
+        assert_eq!(decode_cursor(&encoded).unwrap(), cursor);
+    }
+
+    #[test]
+    fn rejects_malformed_cursors() {
+        assert!(matches!(decode_cursor("not base64!"), Err(AppError::InvalidCursor)));
+        let not_a_cursor = URL_SAFE_NO_PAD.encode(br#"{"sort":"email"}"#);
+        assert!(matches!(decode_cursor(&not_a_cursor), Err(AppError::InvalidCursor)));
+    }
+
+    #[test]
+    fn filter_hash_changes_with_filters() {
+        let unfiltered = UserFilter::default();
+        let admins = UserFilter {
+            role: Some(UserRole::Admin),
+            ..UserFilter::default()
+        };
+        assert_eq!(filter_hash(&unfiltered), filter_hash(&UserFilter::default()));
+        assert_ne!(filter_hash(&unfiltered), filter_hash(&admins));
+    }
 } 
\ No newline at end of file
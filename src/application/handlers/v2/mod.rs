@@ -0,0 +1 @@
+pub mod users; 
\ No newline at end of file
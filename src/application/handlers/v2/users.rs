@@ -0,0 +1,192 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use crate::{
+    application::handlers::users::{
+        self as v1, CreateUserRequest, ListUsersQuery, SearchUsersQuery, UpdateUserRequest,
+    },
+    domain::{
+        models::user::{Claims, User, UserRole},
+        models::user_search::{Highlight, UserSearchHit},
+        repositories::{unit_of_work::UnitOfWork, user_repository::UserRepository},
+        services::{password::PasswordService, registration},
+    },
+    infrastructure::error::{AppError, Problem},
+};
+
+// Same use cases as v1; only the representation of a user changed: role and
+// verification status are exposed and timestamps carry a UTC offset.
+#[derive(Serialize, ToSchema)]
+#[schema(as = v2::UserResponse)]
+pub struct UserResponse {
+    pub id: i32,
+    pub email: String,
+    pub name: Option<String>,
+    pub role: UserRole,
+    pub email_verified: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            name: user.name,
+            role: user.role,
+            email_verified: user.is_email_verified,
+            created_at: user.created_at.and_utc(),
+            updated_at: user.updated_at.and_utc(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = v2::UserPage)]
+pub struct UserPage {
+    pub data: Vec<UserResponse>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = v2::SearchHit)]
+pub struct SearchHit {
+    pub user: UserResponse,
+    pub score: f32,
+    pub highlights: Vec<Highlight>,
+}
+
+impl From<UserSearchHit> for SearchHit {
+    fn from(hit: UserSearchHit) -> Self {
+        Self {
+            user: hit.user.into(),
+            score: hit.score,
+            highlights: hit.highlights,
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/users",
+    tag = "users",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key replay the first response")),
+    request_body = CreateUserRequest,
+    responses(
+        (status = 200, description = "User created", body = UserResponse),
+        (status = 409, description = "Email is already registered", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Request validation failed", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_user<U: UnitOfWork>(
+    State(uow): State<U>,
+    State(passwords): State<PasswordService>,
+    Json(payload): Json<CreateUserRequest>,
+) -> Result<Json<UserResponse>, AppError> {
+    let user = registration::register(&uow, &passwords, payload.email, payload.password, UserRole::User).await?;
+    Ok(Json(user.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}",
+    tag = "users",
+    params(("id" = i32, Path, description = "User id")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "User, with its version as ETag", body = UserResponse,
+            headers(("ETag" = String, description = "Pass back as If-Match to update"))),
+        (status = 404, description = "No such user", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_user<T: UserRepository>(
+    State(repo): State<T>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = repo.find_by_id(id).await?
+        .ok_or(AppError::NotFound)?;
+    let etag = v1::etag(user.version);
+    Ok(([(header::ETAG, etag)], Json(UserResponse::from(user))))
+}
+
+#[utoipa::path(
+    put,
+    path = "/users/{id}",
+    tag = "users",
+    params(
+        ("id" = i32, Path, description = "User id"),
+        ("If-Match" = String, Header, description = "ETag from a previous read, or `*` for any version"),
+    ),
+    request_body = UpdateUserRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "User updated", body = UserResponse,
+            headers(("ETag" = String, description = "New version"))),
+        (status = 404, description = "No such user", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "User was modified since it was read", body = Problem, content_type = "application/problem+json"),
+        (status = 428, description = "If-Match header is missing", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_user<T: UserRepository>(
+    State(repo): State<T>,
+    Path(id): Path<i32>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateUserRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let expected_version = v1::expected_version(&headers)?;
+    let user = repo.update(id, payload.email, payload.password, None, expected_version).await?;
+    let etag = v1::etag(user.version);
+    Ok(([(header::ETAG, etag)], Json(UserResponse::from(user))))
+}
+
+// Unlike v1, listing requires a token, as v2 users carry their role and
+// verification state
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "users",
+    params(ListUsersQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "One page of users", body = UserPage),
+        (status = 400, description = "Cursor is malformed or was issued for a different `sort`, `order` or filters", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "`include_deleted` requires an admin token", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_users<T: UserRepository>(
+    State(repo): State<T>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<ListUsersQuery>,
+) -> Result<Json<UserPage>, AppError> {
+    let (users, next_cursor) = v1::list_page(&repo, Some(&claims), query).await?;
+    Ok(Json(UserPage {
+        data: users.into_iter().map(Into::into).collect(),
+        next_cursor,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/search",
+    tag = "users",
+    params(SearchUsersQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Matches, best first", body = Vec<SearchHit>),
+    )
+)]
+pub async fn search_users<T: UserRepository>(
+    State(repo): State<T>,
+    Query(query): Query<SearchUsersQuery>,
+) -> Result<Json<Vec<SearchHit>>, AppError> {
+    let hits = v1::search(&repo, query).await?;
+    Ok(Json(hits.into_iter().map(Into::into).collect()))
+} 
\ No newline at end of file
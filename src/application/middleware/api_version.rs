@@ -0,0 +1,104 @@
+use std::sync::Arc;
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+
+use crate::infrastructure::config::app::{ApiConfig, ApiVersionConfig, API_VERSIONS};
+
+static DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+static SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+// Strips the `/api/<version>` prefix, so per-route settings such as rate
+// limit policies apply to every version of a route
+pub fn unversioned(path: &str) -> &str {
+    API_VERSIONS
+        .iter()
+        .find_map(|version| {
+            path.strip_prefix("/api/")
+                .and_then(|rest| rest.strip_prefix(version))
+                .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+        .unwrap_or(path)
+}
+
+// Lifecycle headers sent on every response of one API version; empty while
+// the version is current
+#[derive(Clone)]
+pub struct VersionLifecycle {
+    headers: Arc<Vec<(HeaderName, HeaderValue)>>,
+}
+
+impl VersionLifecycle {
+    pub fn new(config: &ApiConfig, version: &str) -> Self {
+        let Some(lifecycle) = config.version(version) else {
+            return Self { headers: Arc::new(Vec::new()) };
+        };
+        let successor = API_VERSIONS
+            .iter()
+            .skip_while(|v| **v != version)
+            .nth(1);
+        Self::from_config(lifecycle, successor.copied())
+    }
+
+    // The unversioned aliases of v1, whose successor is v1 itself
+    pub fn unversioned(config: &ApiConfig) -> Self {
+        Self::from_config(&config.unversioned_lifecycle(), API_VERSIONS.first().copied())
+    }
+
+    fn from_config(lifecycle: &ApiVersionConfig, successor: Option<&str>) -> Self {
+        let mut headers = Vec::new();
+        // Validated when the config was loaded
+        let parse = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+                .map(|value| value.with_timezone(&Utc))
+        };
+
+        // RFC 9745: `@` followed by a Unix timestamp
+        if let Some(deprecated_at) = parse(&lifecycle.deprecated_at) {
+            headers.push((DEPRECATION.clone(), header_value(format!("@{}", deprecated_at.timestamp()))));
+        }
+        // RFC 8594: an HTTP-date
+        if let Some(sunset_at) = parse(&lifecycle.sunset_at) {
+            headers.push((SUNSET.clone(), header_value(sunset_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string())));
+        }
+        if headers.is_empty() {
+            return Self { headers: Arc::new(headers) };
+        }
+
+        if let Some(link) = &lifecycle.deprecation_link {
+            headers.push((header::LINK, header_value(format!("<{}>; rel=\"deprecation\"; type=\"text/html\"", link))));
+        }
+        if let Some(successor) = successor {
+            headers.push((header::LINK, header_value(format!("</api/{}>; rel=\"successor-version\"", successor))));
+        }
+
+        Self { headers: Arc::new(headers) }
+    }
+}
+
+fn header_value(value: String) -> HeaderValue {
+    HeaderValue::from_str(&value).expect("lifecycle headers are validated with the config")
+}
+
+// Marks deprecated versions so clients can plan their migration before the
+// version is removed.
+pub async fn version_lifecycle(
+    State(lifecycle): State<VersionLifecycle>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+
+    let headers = response.headers_mut();
+    for (name, value) in lifecycle.headers.iter() {
+        headers.append(name.clone(), value.clone());
+    }
+    response
+} 
\ No newline at end of file
@@ -1,27 +1,78 @@
 use axum::{
-    extract::State,
-    http::{Request, StatusCode},
+    body::Body,
+    extract::{Extension, State},
+    http::Request,
     middleware::Next,
     response::Response,
 };
-use axum::headers::{Authorization, Bearer};
-use axum::TypedHeader;
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    typed_header::TypedHeaderRejection,
+    TypedHeader,
+};
+
+use crate::domain::models::user::{Claims, UserRole};
+use crate::infrastructure::{auth::jwt::JwtService, error::AppError, tls::ClientPrincipal};
 
-use crate::infrastructure::auth::jwt::JwtService;
+type BearerHeader = TypedHeader<Authorization<Bearer>>;
 
-pub async fn auth_middleware<B>(
-    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+// A missing or malformed `Authorization` header is answered like a bad
+// token, as a problem document rather than the extractor's plain text
+pub async fn auth_middleware(
+    bearer: Result<BearerHeader, TypedHeaderRejection>,
     State(jwt_service): State<JwtService>,
-    mut request: Request<B>,
-    next: Next<B>,
-) -> Result<Response, StatusCode> {
+    principal: Option<ClientPrincipal>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let TypedHeader(Authorization(bearer)) = bearer.map_err(|_| AppError::AuthenticationError)?;
     let token = bearer.token();
     
     match jwt_service.verify_token(token) {
         Ok(claims) => {
+            // The request span declares these fields, see `Server::setup_logging`
+            let span = tracing::Span::current();
+            span.record("enduser.id", claims.sub);
+            // Over mutual TLS, audit which client certificate carried the token
+            if let Some(principal) = &principal {
+                span.record("tls.client.subject", principal.common_name.as_str());
+                span.record("tls.client.serial", principal.serial.as_str());
+            }
             request.extensions_mut().insert(claims);
             Ok(next.run(request).await)
         }
-        Err(_) => Err(StatusCode::UNAUTHORIZED),
+        Err(_) => Err(AppError::AuthenticationError),
+    }
+}
+
+// For public routes whose behaviour widens for some callers: attaches the
+// claims when a bearer token is sent, rejects a bad one, and lets anonymous
+// requests through without claims
+pub async fn optional_auth(
+    bearer: Result<Option<BearerHeader>, TypedHeaderRejection>,
+    State(jwt_service): State<JwtService>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    if let Some(TypedHeader(Authorization(bearer))) = bearer.map_err(|_| AppError::AuthenticationError)? {
+        let claims = jwt_service.verify_token(bearer.token())
+            .map_err(|_| AppError::AuthenticationError)?;
+        tracing::Span::current().record("enduser.id", claims.sub);
+        request.extensions_mut().insert(claims);
     }
+
+    Ok(next.run(request).await)
+}
+
+// Must be layered inside `auth_middleware` so the claims are present
+pub async fn require_admin(
+    Extension(claims): Extension<Claims>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    if claims.role != UserRole::Admin {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    Ok(next.run(request).await)
 } 
\ No newline at end of file
@@ -0,0 +1,300 @@
+use std::sync::Arc;
+use std::time::Duration;
+use axum::{
+    body::{self, Body, Bytes},
+    extract::State,
+    http::{header, HeaderName, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha1::{Digest, Sha1};
+
+use crate::application::middleware::security::ClientIp;
+use crate::infrastructure::{
+    auth::jwt::JwtService,
+    config::app::IdempotencyConfig,
+    error::AppError,
+    idempotency::{Begin, IdempotencyStore, StoredResponse},
+};
+
+static IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+static IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+// Headers that belong to the stored result; everything else (request id,
+// rate limit, trace context) describes the retry and is set afresh
+const REPLAYED_HEADERS: [HeaderName; 3] = [header::CONTENT_TYPE, header::ETAG, header::LOCATION];
+
+// Replays the first response to an `Idempotency-Key` so a retried POST or
+// PATCH doesn't run twice. Keys are scoped to the user of a valid bearer
+// token, else to the client address, so anonymous clients can't replay each
+// other's responses. Login is never stored, as its response carries an
+// access token.
+#[derive(Clone)]
+pub struct Idempotency {
+    store: Arc<dyn IdempotencyStore>,
+    jwt_service: JwtService,
+    ttl: Duration,
+    max_body_bytes: usize,
+}
+
+impl Idempotency {
+    pub fn new(
+        store: Arc<dyn IdempotencyStore>,
+        jwt_service: JwtService,
+        config: &IdempotencyConfig,
+        max_body_bytes: u64,
+    ) -> Self {
+        Self {
+            store,
+            jwt_service,
+            ttl: Duration::from_secs(config.ttl_secs),
+            max_body_bytes: max_body_bytes as usize,
+        }
+    }
+
+    fn scope(&self, request: &Request<Body>) -> String {
+        let user = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| self.jwt_service.verify_token(token).ok())
+            .map(|claims| format!("user:{}", claims.sub));
+        if let Some(user) = user {
+            return user;
+        }
+        match request.extensions().get::<ClientIp>() {
+            Some(ClientIp(ip)) => format!("ip:{}", ip),
+            None => "anonymous".to_string(),
+        }
+    }
+
+    async fn blocking<T, F>(&self, f: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn IdempotencyStore) -> Result<T, AppError> + Send + 'static,
+    {
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || f(store.as_ref()))
+            .await
+            .map_err(|_| AppError::InternalServerError)
+            .and_then(|result| result)
+    }
+}
+
+// Printable ASCII without spaces, as for other opaque header tokens
+fn parse_key(value: &HeaderValue) -> Result<String, AppError> {
+    let key = value.as_bytes();
+    if key.is_empty() || key.len() > 255 || !key.iter().all(|b| b.is_ascii_graphic()) {
+        return Err(AppError::InvalidIdempotencyKey);
+    }
+    Ok(String::from_utf8_lossy(key).into_owned())
+}
+
+// Responses holding access tokens must not be persisted. Registration only
+// echoes the new user's id and email, so a retried register is replayed
+// instead of failing with `user_already_exists`.
+fn issues_tokens(path: &str) -> bool {
+    path.ends_with("/auth/login")
+}
+
+// Identifies "the same request": method, target and exact body
+fn fingerprint(method: &Method, uri: &str, body: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(method.as_str());
+    hasher.update(b" ");
+    hasher.update(uri);
+    hasher.update(b"\n");
+    hasher.update(body);
+    format!("{:x}", hasher.finalize())
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let headers = response.headers_mut();
+    for (name, value) in stored.headers {
+        if let (Ok(name), Ok(value)) = (name.parse::<HeaderName>(), HeaderValue::from_str(&value)) {
+            headers.insert(name, value);
+        }
+    }
+    headers.insert(IDEMPOTENT_REPLAYED.clone(), HeaderValue::from_static("true"));
+    response
+}
+
+pub async fn idempotency(
+    State(idempotency): State<Idempotency>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    if !matches!(*request.method(), Method::POST | Method::PATCH) || issues_tokens(request.uri().path()) {
+        return Ok(next.run(request).await);
+    }
+    let Some(key) = request.headers().get(&IDEMPOTENCY_KEY) else {
+        return Ok(next.run(request).await);
+    };
+    let key = format!("{}|{}", idempotency.scope(&request), parse_key(key)?);
+
+    let (parts, body) = request.into_parts();
+    let body: Bytes = body::to_bytes(body, idempotency.max_body_bytes)
+        .await
+        .map_err(|_| AppError::PayloadTooLarge)?;
+    let fingerprint = fingerprint(&parts.method, &parts.uri.to_string(), &body);
+    let request = Request::from_parts(parts, Body::from(body));
+
+    let begin = {
+        let key = key.clone();
+        idempotency.blocking(move |store| store.begin(&key, &fingerprint)).await
+    };
+    // Fail open like the rate limiter: a broken store shouldn't take the
+    // API down, at the cost of retries not being deduplicated meanwhile
+    let begin = match begin {
+        Ok(begin) => begin,
+        Err(e) => {
+            tracing::warn!("Idempotency check failed, running request: {}", e);
+            return Ok(next.run(request).await);
+        }
+    };
+    match begin {
+        Begin::Started => {}
+        Begin::Replay(stored) => return Ok(replay(stored)),
+        Begin::InFlight => return Err(AppError::IdempotencyKeyInProgress),
+        Begin::Mismatch => return Err(AppError::IdempotencyKeyReused),
+    }
+
+    let response = next.run(request).await;
+
+    // Server errors may be transient, so the retry gets to run again
+    let (parts, body) = response.into_parts();
+    let body = match body::to_bytes(body, usize::MAX).await {
+        Ok(body) if !parts.status.is_server_error() => body,
+        result => {
+            let abandon_key = key.clone();
+            if let Err(e) = idempotency.blocking(move |store| store.abandon(&abandon_key)).await {
+                tracing::warn!("Failed to release idempotency key: {}", e);
+            }
+            return Ok(match result {
+                Ok(body) => Response::from_parts(parts, Body::from(body)),
+                Err(_) => AppError::InternalServerError.into_response(),
+            });
+        }
+    };
+
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        headers: REPLAYED_HEADERS
+            .iter()
+            .filter_map(|name| {
+                let value = parts.headers.get(name)?.to_str().ok()?;
+                Some((name.to_string(), value.to_string()))
+            })
+            .collect(),
+        body: body.to_vec(),
+    };
+    let ttl = idempotency.ttl;
+    if let Err(e) = idempotency.blocking(move |store| store.complete(&key, &stored, ttl)).await {
+        tracing::warn!("Failed to store idempotent response: {}", e);
+    }
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use axum::{middleware, routing::post, Json, Router};
+    use tower::Service;
+    use super::*;
+    use crate::infrastructure::{
+        config::app::AuthConfig,
+        idempotency::MemoryStore,
+        secrets::secret::Secret,
+    };
+
+    fn app(calls: Arc<AtomicUsize>) -> Router {
+        let jwt_service = JwtService::new(&AuthConfig {
+            jwt_secret: Secret::new("test-secret-test-secret-test-secret".to_string()),
+            ..AuthConfig::default()
+        });
+        let state = Idempotency::new(
+            Arc::new(MemoryStore::default()),
+            jwt_service,
+            &IdempotencyConfig::default(),
+            1024,
+        );
+        let handler = move |path: &'static str| {
+            let calls = calls.clone();
+            move || async move {
+                let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                Json(serde_json::json!({ "path": path, "call": n }))
+            }
+        };
+
+        Router::new()
+            .route("/api/v1/auth/register", post(handler("register")))
+            .route("/api/v1/auth/login", post(handler("login")))
+            .layer(middleware::from_fn_with_state(state, idempotency))
+    }
+
+    fn request(path: &str, key: &str) -> Request<Body> {
+        Request::post(path)
+            .header(&IDEMPOTENCY_KEY, key)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"email":"a@example.com","password":"Secret1!"}"#))
+            .unwrap()
+    }
+
+    async fn send(app: &mut Router, request: Request<Body>) -> (Option<HeaderValue>, Bytes) {
+        let response = app.call(request).await.unwrap();
+        let replayed = response.headers().get(&IDEMPOTENT_REPLAYED).cloned();
+        (replayed, body::to_bytes(response.into_body(), usize::MAX).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn replays_a_retried_register() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut app = app(calls.clone());
+
+        let (replayed, first) = send(&mut app, request("/api/v1/auth/register", "retry-1")).await;
+        assert!(replayed.is_none());
+        let (replayed, second) = send(&mut app, request("/api/v1/auth/register", "retry-1")).await;
+        assert_eq!(replayed, Some(HeaderValue::from_static("true")));
+
+        assert_eq!(first, second);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn never_stores_login_responses() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut app = app(calls.clone());
+
+        send(&mut app, request("/api/v1/auth/login", "retry-1")).await;
+        let (replayed, _) = send(&mut app, request("/api/v1/auth/login", "retry-1")).await;
+
+        assert!(replayed.is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+    #[test]
+    fn accepts_only_printable_ascii_keys_up_to_255_bytes() {
+        let key = |value: &str| parse_key(&HeaderValue::from_str(value).unwrap());
+
+        assert_eq!(key("order-42_retry.1").unwrap(), "order-42_retry.1");
+        assert!(key(&"k".repeat(255)).is_ok());
+        assert!(matches!(key(""), Err(AppError::InvalidIdempotencyKey)));
+        assert!(matches!(key(&"k".repeat(256)), Err(AppError::InvalidIdempotencyKey)));
+        assert!(matches!(key("two words"), Err(AppError::InvalidIdempotencyKey)));
+        assert!(matches!(key("tab\tkey"), Err(AppError::InvalidIdempotencyKey)));
+    }
+
+    #[test]
+    fn fingerprint_covers_method_target_and_body() {
+        let base = fingerprint(&Method::POST, "/api/v1/users", b"{}");
+
+        assert_eq!(base, fingerprint(&Method::POST, "/api/v1/users", b"{}"));
+        assert_eq!(base.len(), 40);
+        assert_ne!(base, fingerprint(&Method::PUT, "/api/v1/users", b"{}"));
+        assert_ne!(base, fingerprint(&Method::POST, "/api/v1/users?dry_run=true", b"{}"));
+        assert_ne!(base, fingerprint(&Method::POST, "/api/v1/users", b"{ }"));
+    }
+} 
\ No newline at end of file
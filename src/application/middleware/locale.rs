@@ -0,0 +1,33 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::infrastructure::{i18n, request_context};
+
+// Negotiates the response locale from a `lang` query parameter (explicit
+// preference) or `Accept-Language`, and reports it via `Content-Language`.
+pub async fn locale(
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let preferred = request
+        .uri()
+        .query()
+        .and_then(|q| q.split('&').find_map(|pair| pair.strip_prefix("lang=")))
+        .map(str::to_string);
+    let accept_language = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok());
+
+    let langid = i18n::negotiate(preferred.as_deref(), accept_language);
+
+    let mut response = request_context::with_locale(langid.clone(), next.run(request)).await;
+    if let Ok(value) = HeaderValue::from_str(&langid.to_string()) {
+        response.headers_mut().insert(header::CONTENT_LANGUAGE, value);
+    }
+    response
+} 
\ No newline at end of file
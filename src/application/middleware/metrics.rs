@@ -0,0 +1,58 @@
+use std::time::Instant;
+use axum::{
+    body::Body,
+    extract::MatchedPath,
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use crate::infrastructure::metrics::{HTTP_REQUESTS_TOTAL, HTTP_REQUEST_DURATION_SECONDS};
+
+// Route template a response was produced for, passed out to `track_metrics`
+#[derive(Clone)]
+struct MatchedRoute(String);
+
+// Route layer: only inside the router is the matched template known
+pub async fn record_matched_route(
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| MatchedRoute(path.as_str().to_string()));
+
+    let mut response = next.run(request).await;
+    if let Some(route) = route {
+        response.extensions_mut().insert(route);
+    }
+    response
+}
+
+// Counts requests and records latency by route template (`/users/{id}`, not
+// the concrete path) so label cardinality stays bounded. Layered outside the
+// rate limiter and hardening so the responses they reject with are counted
+// too; those never reach a route and are labelled `unmatched`.
+pub async fn track_metrics(
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    let route = response
+        .extensions()
+        .get::<MatchedRoute>()
+        .map(|route| route.0.as_str())
+        .unwrap_or("unmatched");
+    let status = response.status().as_u16().to_string();
+    let labels = [method.as_str(), route, status.as_str()];
+    HTTP_REQUESTS_TOTAL.with_label_values(&labels).inc();
+    HTTP_REQUEST_DURATION_SECONDS
+        .with_label_values(&labels)
+        .observe(started.elapsed().as_secs_f64());
+
+    response
+} 
\ No newline at end of file
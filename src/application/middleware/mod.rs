@@ -0,0 +1,10 @@
+pub mod api_version;
+pub mod auth;
+pub mod idempotency;
+pub mod locale;
+pub mod metrics;
+pub mod rate_limit;
+pub mod read_your_writes;
+pub mod request_id;
+pub mod security;
+pub mod trace_context;
@@ -0,0 +1,157 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha1::{Digest, Sha1};
+
+use crate::application::middleware::{api_version, security::ClientIp};
+use crate::infrastructure::{
+    auth::jwt::JwtService,
+    config::{app::RateLimitConfig, reload::SharedSettings},
+    error::AppError,
+    metrics::RATE_LIMIT_BACKEND_ERRORS_TOTAL,
+    rate_limit::{Decision, Quota, RateLimitStore},
+};
+
+static RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+static RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+static RATE_LIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+static RATE_LIMIT_POLICY: HeaderName = HeaderName::from_static("ratelimit-policy");
+
+// Token-bucket limiter keyed per client and per route policy. Policies are
+// read from the live settings on every request, so a config reload takes
+// effect immediately.
+#[derive(Clone)]
+pub struct RateLimiter {
+    settings: SharedSettings,
+    store: Arc<dyn RateLimitStore>,
+    jwt_service: JwtService,
+}
+
+impl RateLimiter {
+    pub fn new(settings: SharedSettings, store: Arc<dyn RateLimitStore>, jwt_service: JwtService) -> Self {
+        Self {
+            settings,
+            store,
+            jwt_service,
+        }
+    }
+
+    // A configured API key if one is sent, else the user of a valid bearer
+    // token, else the peer address. Unknown keys are ignored, so made-up keys
+    // cannot be used to get fresh buckets. Keys are hashed so they are never
+    // stored.
+    fn client_key(&self, config: &RateLimitConfig, request: &Request<Body>) -> String {
+        let headers = request.headers();
+
+        let api_key = headers
+            .get(config.api_key_header.as_str())
+            .map(|value| Sha1::digest(value.as_bytes()))
+            .filter(|digest| {
+                config.api_keys.iter().any(|key| Sha1::digest(key.expose().as_bytes()) == *digest)
+            });
+        if let Some(digest) = api_key {
+            return format!("key:{:x}", digest);
+        }
+
+        let claims = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| self.jwt_service.verify_token(token).ok());
+        if let Some(claims) = claims {
+            return format!("user:{}", claims.sub);
+        }
+
+        // Resolved through trusted proxies by the security middleware
+        if let Some(ClientIp(ip)) = request.extensions().get::<ClientIp>() {
+            return format!("ip:{}", ip);
+        }
+
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| format!("ip:{}", addr.ip()))
+            .unwrap_or_else(|| "ip:unknown".to_string())
+    }
+}
+
+// Longest matching `path_prefix`, falling back to the default policy. Paths
+// are matched without their version, so all versions share a bucket.
+fn policy_for<'a>(config: &'a RateLimitConfig, path: &str) -> (&'a str, Quota) {
+    config
+        .policies
+        .iter()
+        .filter(|policy| path.starts_with(&policy.path_prefix))
+        .max_by_key(|policy| policy.path_prefix.len())
+        .map(|policy| (policy.path_prefix.as_str(), policy.requests, policy.duration_secs))
+        .map(|(name, requests, secs)| (name, Quota { capacity: requests, period: Duration::from_secs(secs) }))
+        .unwrap_or(("default", Quota {
+            capacity: config.requests,
+            period: Duration::from_secs(config.duration_secs),
+        }))
+}
+
+fn insert_headers(headers: &mut HeaderMap, quota: Quota, decision: &Decision) {
+    let values = [
+        (&RATE_LIMIT_LIMIT, quota.capacity.to_string()),
+        (&RATE_LIMIT_REMAINING, decision.remaining.to_string()),
+        (&RATE_LIMIT_RESET, decision.reset_after.as_secs_f64().ceil().to_string()),
+        (&RATE_LIMIT_POLICY, format!("{};w={}", quota.capacity, quota.period.as_secs())),
+    ];
+    for (name, value) in values {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name.clone(), value);
+        }
+    }
+    if let Some(retry_after) = decision.retry_after {
+        headers.insert(header::RETRY_AFTER, HeaderValue::from(retry_after.as_secs_f64().ceil() as u64));
+    }
+}
+
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let settings = limiter.settings.load_full();
+    let config = &settings.rate_limit;
+    let (policy, quota) = policy_for(config, api_version::unversioned(request.uri().path()));
+    let key = format!("{}|{}", policy, limiter.client_key(config, &request));
+
+    let store = limiter.store.clone();
+    let decision = tokio::task::spawn_blocking(move || store.acquire(&key, quota))
+        .await
+        .map_err(|_| AppError::InternalServerError)
+        .and_then(|decision| decision);
+
+    // By default fail open: a broken limiter backend shouldn't take the API
+    // down. The metric makes an outage visible either way.
+    let decision = match decision {
+        Ok(decision) => decision,
+        Err(e) if config.fail_open => {
+            RATE_LIMIT_BACKEND_ERRORS_TOTAL.with_label_values(&["allowed"]).inc();
+            tracing::warn!("Rate limit check failed, allowing request: {}", e);
+            return next.run(request).await;
+        }
+        Err(e) => {
+            RATE_LIMIT_BACKEND_ERRORS_TOTAL.with_label_values(&["rejected"]).inc();
+            tracing::error!("Rate limit check failed, rejecting request: {}", e);
+            return AppError::ServiceUnavailable.into_response();
+        }
+    };
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        AppError::RateLimitExceeded.into_response()
+    };
+    insert_headers(response.headers_mut(), quota, &decision);
+    response
+} 
\ No newline at end of file
@@ -0,0 +1,17 @@
+use axum::{
+    body::Body,
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+
+use crate::infrastructure::db::replica;
+
+// Gives each request its own read-your-writes scope so reads following a
+// write in the same request are not served by a lagging replica.
+pub async fn read_your_writes(
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    replica::sticky_scope(next.run(request)).await
+}
@@ -0,0 +1,186 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http_body_util::Limited;
+use ipnet::IpNet;
+
+use crate::application::middleware::api_version;
+use crate::infrastructure::{config::app::SecurityConfig, error::AppError};
+
+static X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+// Address of the client that made the request, after `X-Forwarded-For`
+// from trusted proxies has been taken into account
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+// Parsed once from `SecurityConfig` at startup
+#[derive(Clone)]
+pub struct Hardening {
+    config: Arc<SecurityConfig>,
+    headers: Arc<Vec<(HeaderName, HeaderValue)>>,
+    trusted_proxies: Arc<Vec<IpNet>>,
+}
+
+impl Hardening {
+    pub fn new(config: &SecurityConfig) -> Self {
+        let mut headers = vec![
+            (header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+        ];
+        if config.hsts_max_age_secs > 0 {
+            headers.push((
+                header::STRICT_TRANSPORT_SECURITY,
+                HeaderValue::from_str(&format!("max-age={}; includeSubDomains", config.hsts_max_age_secs))
+                    .expect("numeric header value"),
+            ));
+        }
+        // Validated when the config was loaded
+        for (name, value) in [
+            (header::CONTENT_SECURITY_POLICY, &config.content_security_policy),
+            (header::REFERRER_POLICY, &config.referrer_policy),
+            (header::X_FRAME_OPTIONS, &config.frame_options),
+        ] {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.push((name, value));
+            }
+        }
+
+        let trusted_proxies = config
+            .trusted_proxies
+            .iter()
+            .filter_map(|proxy| proxy.parse::<IpNet>().ok().or_else(|| proxy.parse::<IpAddr>().ok().map(IpNet::from)))
+            .collect();
+
+        Self {
+            config: Arc::new(config.clone()),
+            headers: Arc::new(headers),
+            trusted_proxies: Arc::new(trusted_proxies),
+        }
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+
+    // Walks `X-Forwarded-For` right to left while the hops are trusted
+    // proxies; the first untrusted hop is the client. Without a trusted peer
+    // the header is ignored, since anyone can send it.
+    fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+
+        let hops: Vec<IpAddr> = headers
+            .get_all(&X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|hop| hop.trim().parse().ok())
+            .collect();
+
+        hops.iter()
+            .rev()
+            .find(|hop| !self.is_trusted(**hop))
+            .or_else(|| hops.first())
+            .copied()
+            .unwrap_or(peer)
+    }
+
+    fn max_body_bytes(&self, path: &str) -> u64 {
+        self.config
+            .route(path)
+            .and_then(|route| route.max_body_bytes)
+            .unwrap_or(self.config.max_body_bytes)
+    }
+
+    fn check_body(&self, path: &str, method: &Method, headers: &HeaderMap) -> Result<(), AppError> {
+        let route = self.config.route(path);
+        let max_body_bytes = self.max_body_bytes(path);
+
+        let content_length = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if content_length.is_some_and(|length| length > max_body_bytes) {
+            return Err(AppError::PayloadTooLarge);
+        }
+
+        let has_body = content_length.map_or(headers.contains_key(header::TRANSFER_ENCODING), |length| length > 0);
+        if !has_body || !matches!(*method, Method::POST | Method::PUT | Method::PATCH) {
+            return Ok(());
+        }
+
+        let allowed = route
+            .and_then(|route| route.content_types.as_ref())
+            .unwrap_or(&self.config.content_types);
+        let media_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase());
+
+        match media_type {
+            Some(media_type) if allowed.iter().any(|a| a.eq_ignore_ascii_case(&media_type)) => Ok(()),
+            _ => Err(AppError::UnsupportedMediaType),
+        }
+    }
+
+    fn timeout(&self, path: &str) -> Duration {
+        Duration::from_secs(
+            self.config
+                .route(path)
+                .and_then(|route| route.request_timeout_secs)
+                .unwrap_or(self.config.request_timeout_secs),
+        )
+    }
+}
+
+// Outermost middleware: resolves the client IP, rejects oversized or
+// unexpected bodies before any work is done, bounds handler time and adds
+// security headers to every response, errors included. Bodies are also
+// capped as they are read, since Content-Length may be absent or wrong.
+pub async fn harden(
+    State(hardening): State<Hardening>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    if let Some(ConnectInfo(peer)) = request.extensions().get::<ConnectInfo<SocketAddr>>().copied() {
+        let client_ip = hardening.client_ip(peer.ip(), request.headers());
+        request.extensions_mut().insert(ClientIp(client_ip));
+    }
+
+    // Route settings apply to every API version
+    let path = api_version::unversioned(request.uri().path()).to_string();
+    let max_body_bytes = hardening.max_body_bytes(&path) as usize;
+    let request = request.map(|body| Body::new(Limited::new(body, max_body_bytes)));
+    let mut response = match hardening.check_body(&path, request.method(), request.headers()) {
+        Err(e) => e.into_response(),
+        Ok(()) => match tokio::time::timeout(hardening.timeout(&path), next.run(request)).await {
+            Ok(response) => response,
+            Err(_) => AppError::RequestTimeout.into_response(),
+        },
+    };
+
+    let headers = response.headers_mut();
+    let route_csp = hardening
+        .config
+        .route(&path)
+        .and_then(|route| route.content_security_policy.as_deref())
+        .and_then(|csp| HeaderValue::from_str(csp).ok());
+    if let Some(csp) = route_csp {
+        headers.entry(header::CONTENT_SECURITY_POLICY).or_insert(csp);
+    }
+    for (name, value) in hardening.headers.iter() {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+    response
+} 
\ No newline at end of file
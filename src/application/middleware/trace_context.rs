@@ -0,0 +1,25 @@
+use axum::{
+    body::Body,
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::global;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::infrastructure::telemetry::HeaderInjector;
+
+// Returns the request's trace context as a `traceparent` response header so
+// clients can find the trace for a response.
+pub async fn trace_context(
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+
+    let context = tracing::Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(response.headers_mut()))
+    });
+    response
+} 
\ No newline at end of file
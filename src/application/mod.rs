@@ -1,2 +1,4 @@
 pub mod handlers;
-pub mod middleware; 
\ No newline at end of file
+pub mod middleware;
+pub mod openapi;
+pub mod state; 
\ No newline at end of file
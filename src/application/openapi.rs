@@ -0,0 +1,163 @@
+use utoipa::{
+    openapi::{
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+        path::{Operation, PathItem},
+        ContentBuilder, OpenApi as Spec, RefOr, ResponseBuilder,
+    },
+    Modify, OpenApi,
+};
+use crate::application::handlers::{auth, health, protected, user_transfer, users, v2};
+
+// Committed copy of the generated document; `openapi check` fails when the
+// handlers and DTOs no longer match it
+pub const SPEC_FILE: &str = "openapi.json";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Rust Clean Architecture API"),
+    paths(health::live, health::ready),
+    tags(
+        (name = "auth", description = "Login, registration and email verification"),
+        (name = "users", description = "User management"),
+        (name = "admin", description = "Bulk operations, admin role required"),
+        (name = "health", description = "Liveness and readiness probes"),
+    )
+)]
+pub struct ApiDoc;
+
+// Paths relative to `/api/v1`
+#[derive(OpenApi)]
+#[openapi(paths(
+    auth::login,
+    auth::register,
+    auth::verify_email,
+    auth::resend_verification,
+    protected::handler,
+    users::create_user,
+    users::list_users,
+    users::search_users,
+    users::get_user,
+    users::update_user,
+    users::delete_user,
+    user_transfer::import_users,
+    user_transfer::export_users,
+))]
+struct V1Api;
+
+// Paths relative to `/api/v2`; unchanged routes are shared with v1
+#[derive(OpenApi)]
+#[openapi(paths(
+    auth::login,
+    auth::register,
+    auth::verify_email,
+    auth::resend_verification,
+    protected::handler,
+    v2::users::create_user,
+    v2::users::list_users,
+    v2::users::search_users,
+    v2::users::get_user,
+    v2::users::update_user,
+    users::delete_user,
+    user_transfer::import_users,
+    user_transfer::export_users,
+))]
+struct V2Api;
+
+// Every API version nested under its prefix, plus the unversioned probes
+pub fn spec() -> Spec {
+    let mut spec = ApiDoc::openapi()
+        .nest("/api/v1", V1Api::openapi())
+        .nest("/api/v2", with_operation_suffix(V2Api::openapi(), "_v2"));
+    BearerAuth.modify(&mut spec);
+    ProblemResponses.modify(&mut spec);
+    spec
+}
+
+// Operation ids must be unique across the document, and v2 reuses some v1
+// handlers
+fn with_operation_suffix(mut api: Spec, suffix: &str) -> Spec {
+    for item in api.paths.paths.values_mut() {
+        for operation in operations(item) {
+            if let Some(id) = operation.operation_id.as_mut().filter(|id| !id.ends_with(suffix)) {
+                id.push_str(suffix);
+            }
+        }
+    }
+    api
+}
+
+fn operations(item: &mut PathItem) -> impl Iterator<Item = &mut Operation> {
+    [
+        &mut item.get,
+        &mut item.put,
+        &mut item.post,
+        &mut item.delete,
+        &mut item.patch,
+    ]
+    .into_iter()
+    .flatten()
+}
+
+// Tokens from `/auth/login`, sent as `Authorization: Bearer <token>`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut Spec) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+// Every endpoint can also fail with rate limiting, body limits, timeouts or
+// internal errors, all rendered as problem details by `AppError`
+struct ProblemResponses;
+
+impl Modify for ProblemResponses {
+    fn modify(&self, openapi: &mut Spec) {
+        let problem = ResponseBuilder::new()
+            .description("Error, see `code` for the reason")
+            .content(
+                "application/problem+json",
+                ContentBuilder::new()
+                    .schema(Some(RefOr::Ref(utoipa::openapi::Ref::from_schema_name("Problem"))))
+                    .build(),
+            )
+            .build();
+
+        for item in openapi.paths.paths.values_mut() {
+            for operation in operations(item) {
+                operation
+                    .responses
+                    .responses
+                    .entry("default".to_string())
+                    .or_insert_with(|| RefOr::T(problem.clone()));
+            }
+        }
+    }
+}
+
+pub fn to_json() -> String {
+    spec()
+        .to_pretty_json()
+        .expect("OpenAPI document serializes to JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Same comparison as `openapi check`; run `openapi write` after
+    // reviewing an intended API change
+    #[test]
+    fn committed_spec_is_up_to_date() {
+        assert_eq!(to_json() + "\n", include_str!("../../openapi.json"));
+    }
+} 
\ No newline at end of file
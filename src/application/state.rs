@@ -0,0 +1,53 @@
+use axum::extract::FromRef;
+use crate::{
+    application::handlers::auth::EmailVerification,
+    domain::services::password::PasswordService,
+    infrastructure::{
+        auth::jwt::JwtService,
+        db::transaction::DieselUnitOfWork,
+        repositories::user_repository::DieselUserRepository,
+    },
+};
+
+// State shared by the API handlers; each handler extracts only the parts it
+// needs through `FromRef`
+#[derive(Clone)]
+pub struct AppState {
+    // Routed: reads may go to a replica, writes to the primary
+    pub users: DieselUserRepository,
+    // Always the primary; used by writes spanning several repositories
+    pub unit_of_work: DieselUnitOfWork,
+    pub passwords: PasswordService,
+    pub jwt_service: JwtService,
+    pub email_verification: EmailVerification,
+}
+
+impl FromRef<AppState> for DieselUserRepository {
+    fn from_ref(state: &AppState) -> Self {
+        state.users.clone()
+    }
+}
+
+impl FromRef<AppState> for DieselUnitOfWork {
+    fn from_ref(state: &AppState) -> Self {
+        state.unit_of_work.clone()
+    }
+}
+
+impl FromRef<AppState> for PasswordService {
+    fn from_ref(state: &AppState) -> Self {
+        state.passwords.clone()
+    }
+}
+
+impl FromRef<AppState> for JwtService {
+    fn from_ref(state: &AppState) -> Self {
+        state.jwt_service.clone()
+    }
+}
+
+impl FromRef<AppState> for EmailVerification {
+    fn from_ref(state: &AppState) -> Self {
+        state.email_verification.clone()
+    }
+} 
\ No newline at end of file
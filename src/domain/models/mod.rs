@@ -0,0 +1,4 @@
+pub mod password_policy;
+pub mod user;
+pub mod user_query;
+pub mod user_search;
@@ -0,0 +1,183 @@
+use std::sync::Arc;
+use validator::{ValidationError, ValidationErrors};
+
+const SPECIAL_CHARACTERS: &str = "@$!%*?&#^()-_=+[]{};:'\",.<>/\\|`~";
+
+// Source of known-breached passwords, e.g. a local k-anonymity hash dataset
+pub trait BreachedPasswordCheck: Send + Sync {
+    fn is_breached(&self, password: &str) -> bool;
+}
+
+#[derive(Clone)]
+pub struct PasswordPolicy {
+    // In characters
+    pub min_length: usize,
+    // In UTF-8 bytes, since bcrypt ignores everything past 72 bytes
+    pub max_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+    pub disallow_email: bool,
+    // Number of previous passwords a user may not reuse; 0 disables the check
+    pub history_size: i64,
+    pub breached: Option<Arc<dyn BreachedPasswordCheck>>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 72,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_special: true,
+            disallow_email: true,
+            history_size: 0,
+            breached: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    // Reports every rule the password breaks, not just the first one
+    pub fn validate(&self, password: &str, email: Option<&str>) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if password.chars().count() < self.min_length {
+            let mut err = ValidationError::new("length");
+            err.add_param("min".into(), &self.min_length);
+            err.add_param("max".into(), &self.max_length);
+            errors.add("password", err);
+        }
+        if password.len() > self.max_length {
+            let mut err = ValidationError::new("too_long");
+            err.add_param("max".into(), &self.max_length);
+            errors.add("password", err);
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            errors.add("password", ValidationError::new("uppercase"));
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            errors.add("password", ValidationError::new("lowercase"));
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            errors.add("password", ValidationError::new("digit"));
+        }
+        if self.require_special && !password.chars().any(|c| SPECIAL_CHARACTERS.contains(c)) {
+            errors.add("password", ValidationError::new("special"));
+        }
+
+        if self.disallow_email {
+            let lowered = password.to_lowercase();
+            let contains_email = email
+                .map(|e| e.to_lowercase())
+                .map(|e| {
+                    let local_part = e.split('@').next().unwrap_or_default().to_string();
+                    lowered.contains(&e) || (local_part.len() >= 3 && lowered.contains(&local_part))
+                })
+                .unwrap_or(false);
+
+            if contains_email {
+                errors.add("password", ValidationError::new("contains_email"));
+            }
+        }
+
+        if let Some(breached) = &self.breached {
+            if breached.is_breached(password) {
+                errors.add("password", ValidationError::new("breached"));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn reused_error() -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        errors.add("password", ValidationError::new("reused"));
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(result: Result<(), ValidationErrors>) -> Vec<String> {
+        let Err(errors) = result else {
+            return Vec::new();
+        };
+        errors.field_errors()["password"].iter().map(|e| e.code.to_string()).collect()
+    }
+
+    #[test]
+    fn max_length_counts_bytes() {
+        let policy = PasswordPolicy {
+            max_length: 12,
+            ..PasswordPolicy::default()
+        };
+
+        // 12 characters but 15 bytes
+        assert_eq!(codes(policy.validate("Pässwörd!1üa", None)), vec!["too_long"]);
+        assert!(policy.validate("Password!1ab", None).is_ok());
+    }
+
+    #[test]
+    fn min_length_counts_characters() {
+        let policy = PasswordPolicy {
+            min_length: 8,
+            ..PasswordPolicy::default()
+        };
+
+        // 7 characters, 10 bytes
+        assert_eq!(codes(policy.validate("Pä!1öüA", None)), vec!["length"]);
+        assert!(policy.validate("Pä!1öüAb", None).is_ok());
+    }
+
+    #[test]
+    fn reports_every_broken_rule() {
+        let policy = PasswordPolicy::default();
+
+        assert_eq!(
+            codes(policy.validate("short", None)),
+            vec!["length", "uppercase", "digit", "special"],
+        );
+        assert_eq!(codes(policy.validate("ALLCAPS123!", None)), vec!["lowercase"]);
+    }
+
+    #[test]
+    fn rejects_passwords_containing_the_email() {
+        let policy = PasswordPolicy::default();
+
+        assert_eq!(codes(policy.validate("Alice!2024x", Some("alice@example.com"))), vec!["contains_email"]);
+        // Local parts shorter than three characters are too common to match
+        assert!(policy.validate("Al!2024xyz", Some("al@example.com")).is_ok());
+
+        let lenient = PasswordPolicy {
+            disallow_email: false,
+            ..PasswordPolicy::default()
+        };
+        assert!(lenient.validate("Alice!2024x", Some("alice@example.com")).is_ok());
+    }
+
+    #[test]
+    fn consults_the_breached_password_check() {
+        struct Breached;
+        impl BreachedPasswordCheck for Breached {
+            fn is_breached(&self, password: &str) -> bool {
+                password == "P@ssw0rd123"
+            }
+        }
+
+        let policy = PasswordPolicy {
+            breached: Some(Arc::new(Breached)),
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(codes(policy.validate("P@ssw0rd123", None)), vec!["breached"]);
+        assert!(policy.validate("Unbr3ached!pw", None).is_ok());
+    }
+} 
\ No newline at end of file
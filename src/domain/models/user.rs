@@ -1,16 +1,50 @@
-use diesel::prelude::*;
+use std::fmt;
+use std::io::Write;
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow},
+    expression::AsExpression,
+    pg::{Pg, PgValue},
+    prelude::*,
+    serialize::{self, IsNull, Output, ToSql},
+};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema, AsExpression, FromSqlRow)]
+#[diesel(sql_type = crate::schema::sql_types::UserRole)]
 pub enum UserRole {
     Admin,
     User,
 }
 
-#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Validate)]
-#[diesel(table_name = users)]
+// Stored as the `user_role` Postgres enum
+impl ToSql<crate::schema::sql_types::UserRole, Pg> for UserRole {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let value: &[u8] = match self {
+            UserRole::Admin => b"admin",
+            UserRole::User => b"user",
+        };
+        out.write_all(value)?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<crate::schema::sql_types::UserRole, Pg> for UserRole {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"admin" => Ok(UserRole::Admin),
+            b"user" => Ok(UserRole::User),
+            other => Err(format!("Unknown user_role: {}", String::from_utf8_lossy(other)).into()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Queryable, Insertable, Validate)]
+#[diesel(table_name = crate::schema::users)]
 pub struct User {
+    // Assigned by the database on insert
+    #[diesel(skip_insertion)]
     pub id: i32,
     #[validate(email(message = "Invalid email format"))]
     pub email: String,
@@ -19,9 +53,38 @@ pub struct User {
     pub is_email_verified: bool,
     pub deleted_at: Option<chrono::NaiveDateTime>,
     pub created_at: chrono::NaiveDateTime,
+    // Bumped on every update; used for optimistic concurrency control
+    pub version: i32,
+    pub updated_at: chrono::NaiveDateTime,
+    // Profile display name
+    pub name: Option<String>,
+}
+
+// Written by hand so the password hash never ends up in logs
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("User")
+            .field("id", &self.id)
+            .field("email", &self.email)
+            .field("password", &"[REDACTED]")
+            .field("role", &self.role)
+            .field("is_email_verified", &self.is_email_verified)
+            .field("deleted_at", &self.deleted_at)
+            .field("created_at", &self.created_at)
+            .field("version", &self.version)
+            .field("updated_at", &self.updated_at)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub enum UpsertOutcome {
+    Created,
+    Updated,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: i32, // user id
     pub role: UserRole,
@@ -29,19 +92,12 @@ pub struct Claims {
     pub iat: usize,
 }
 
-// Password validation struct
-#[derive(Debug, Validate)]
-pub struct PasswordRequirements {
-    #[validate(length(min = 8, message = "Password must be at least 8 characters long"))]
-    #[validate(regex(
-        path = "PASSWORD_REGEX",
-        message = "Password must contain at least one uppercase letter, one lowercase letter, one number, and one special character"
-    ))]
-    pub password: String,
-}
-
-lazy_static::lazy_static! {
-    static ref PASSWORD_REGEX: regex::Regex = regex::Regex::new(
-        r"^(?=.*[a-z])(?=.*[A-Z])(?=.*\d)(?=.*[@$!%*?&])[A-Za-z\d@$!%*?&]{8,}$"
-    ).unwrap();
+// Signed into the link of the verification email. `purpose` keeps access
+// tokens and verification tokens from being accepted in place of each other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailVerificationClaims {
+    pub sub: i32, // user id
+    pub purpose: String,
+    pub exp: usize,
+    pub iat: usize,
 } 
\ No newline at end of file
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::domain::models::user::UserRole;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSortField {
+    CreatedAt,
+    Email,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UserFilter {
+    pub role: Option<UserRole>,
+    pub is_email_verified: Option<bool>,
+    pub created_after: Option<chrono::NaiveDateTime>,
+    pub created_before: Option<chrono::NaiveDateTime>,
+    pub email_contains: Option<String>,
+    pub email_or_name_contains: Option<String>,
+    pub include_deleted: bool,
+}
+
+// Sort key of the last row on the previous page. `id` breaks ties so that
+// keyset pagination stays stable when sort values repeat.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "sort", rename_all = "snake_case")]
+pub enum UserCursor {
+    CreatedAt { created_at: chrono::NaiveDateTime, id: i32 },
+    Email { email: String, id: i32 },
+}
+
+impl UserCursor {
+    pub fn sort_field(&self) -> UserSortField {
+        match self {
+            UserCursor::CreatedAt { .. } => UserSortField::CreatedAt,
+            UserCursor::Email { .. } => UserSortField::Email,
+        }
+    }
+
+    pub fn for_user(sort: UserSortField, user: &crate::domain::models::user::User) -> Self {
+        match sort {
+            UserSortField::CreatedAt => UserCursor::CreatedAt {
+                created_at: user.created_at,
+                id: user.id,
+            },
+            UserSortField::Email => UserCursor::Email {
+                email: user.email.clone(),
+                id: user.id,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UserListQuery {
+    pub filter: UserFilter,
+    pub sort: UserSortField,
+    pub direction: SortDirection,
+    pub after: Option<UserCursor>,
+    pub limit: i64,
+}
@@ -0,0 +1,74 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+use crate::domain::models::user::User;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, ToSchema)]
+pub struct Highlight {
+    pub field: &'static str,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug)]
+pub struct UserSearchHit {
+    pub user: User,
+    pub score: f32,
+    pub highlights: Vec<Highlight>,
+}
+
+impl UserSearchHit {
+    pub fn new(user: User, term: &str, score: f32) -> Self {
+        let mut highlights = highlight("email", &user.email, term);
+        if let Some(name) = &user.name {
+            highlights.extend(highlight("name", name, term));
+        }
+        Self { user, score, highlights }
+    }
+}
+
+// Byte ranges of every case-insensitive occurrence of `term` in `value`
+pub fn highlight(field: &'static str, value: &str, term: &str) -> Vec<Highlight> {
+    if term.is_empty() {
+        return Vec::new();
+    }
+
+    let haystack = value.to_lowercase();
+    let needle = term.to_lowercase();
+    // Lowercasing can change byte lengths for non-ASCII input; only report
+    // ranges when offsets still line up with the original value.
+    if haystack.len() != value.len() {
+        return Vec::new();
+    }
+
+    haystack
+        .match_indices(&needle)
+        .map(|(start, matched)| Highlight {
+            field,
+            start,
+            end: start + matched.len(),
+        })
+        .collect()
+}
+
+// Best substring score across the searchable fields
+pub fn user_score(user: &User, term: &str) -> f32 {
+    let name_score = user.name.as_deref().map_or(0.0, |name| substring_score(name, term));
+    substring_score(&user.email, term).max(name_score)
+}
+
+// Cheap relevance score used when the backend has no trigram support:
+// exact match > prefix > substring.
+pub fn substring_score(value: &str, term: &str) -> f32 {
+    let value = value.to_lowercase();
+    let term = term.to_lowercase();
+
+    if value == term {
+        1.0
+    } else if value.starts_with(&term) {
+        0.75
+    } else if value.contains(&term) {
+        0.5 * term.len() as f32 / value.len().max(1) as f32 + 0.25
+    } else {
+        0.0
+    }
+}
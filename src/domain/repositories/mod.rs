@@ -0,0 +1,3 @@
+pub mod password_history_repository;
+pub mod user_repository;
+pub mod unit_of_work;
@@ -0,0 +1,8 @@
+use async_trait::async_trait;
+use crate::infrastructure::error::AppError;
+
+// Hashes of passwords a user has had, used to stop them being reused
+#[async_trait]
+pub trait PasswordHistoryRepository: Send + Sync + 'static {
+    async fn record(&self, user_id: i32, password_hash: &str) -> Result<(), AppError>;
+} 
\ No newline at end of file
@@ -0,0 +1,50 @@
+use std::future::Future;
+use std::pin::Pin;
+use async_trait::async_trait;
+use crate::domain::repositories::{
+    password_history_repository::PasswordHistoryRepository,
+    user_repository::UserRepository,
+};
+use crate::infrastructure::error::AppError;
+
+pub type TxFuture<'a, R> = Pin<Box<dyn Future<Output = Result<R, AppError>> + Send + 'a>>;
+
+// A single database transaction. Repositories handed out by the transaction
+// all run on the same connection until `commit` or `rollback` is called.
+#[async_trait]
+pub trait Transaction: Send + Sync + Sized + 'static {
+    type Users: UserRepository;
+    type PasswordHistory: PasswordHistoryRepository;
+
+    fn users(&self) -> &Self::Users;
+    fn password_history(&self) -> &Self::PasswordHistory;
+    async fn commit(self) -> Result<(), AppError>;
+    async fn rollback(self) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait UnitOfWork: Send + Sync + 'static {
+    type Tx: Transaction;
+
+    async fn begin(&self) -> Result<Self::Tx, AppError>;
+
+    // Runs `f` inside a transaction, committing on `Ok` and rolling back on `Err`.
+    async fn transaction<R, F>(&self, f: F) -> Result<R, AppError>
+    where
+        R: Send + 'static,
+        F: for<'a> FnOnce(&'a Self::Tx) -> TxFuture<'a, R> + Send,
+    {
+        let tx = self.begin().await?;
+
+        match f(&tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+                Ok(value)
+            }
+            Err(e) => {
+                tx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+}
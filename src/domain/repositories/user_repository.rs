@@ -1,10 +1,17 @@
 use async_trait::async_trait;
-use crate::domain::models::user::{User, UserRole};
+use crate::domain::models::user::{UpsertOutcome, User, UserRole};
+use crate::domain::models::user_query::{SortDirection, UserFilter, UserListQuery, UserSortField};
+use crate::domain::models::user_search::{self, UserSearchHit};
 use crate::infrastructure::error::AppError;
 
+// Most candidates the default `search` loads to rank in memory
+const MAX_SEARCH_CANDIDATES: i64 = 1000;
+
 #[async_trait]
 pub trait UserRepository: Send + Sync + 'static {
-    async fn create(&self, email: String, password: String, role: UserRole) -> Result<User, AppError>;
+    // Inserts the user as-is; password policy and history are the caller's job
+    // (see `domain::services::registration`)
+    async fn create(&self, email: String, password_hash: String, role: UserRole) -> Result<User, AppError>;
     async fn find_by_id(&self, id: i32) -> Result<Option<User>, AppError>;
     async fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError>;
     async fn update(
@@ -13,8 +20,52 @@ pub trait UserRepository: Send + Sync + 'static {
         email: Option<String>, 
         password: Option<String>,
         role: Option<UserRole>,
+        // `None` skips the version check
+        expected_version: Option<i32>,
     ) -> Result<User, AppError>;
+    // Creates the user or updates the existing one with the same email,
+    // atomically; soft-deleted accounts are left alone (`AppError::Conflict`).
+    // Takes an already hashed password so bulk callers control hashing. The
+    // new hash is recorded in the password history but never checked against
+    // it: bcrypt hashes can't be compared, and imports are an admin override.
+    async fn upsert_by_email(
+        &self,
+        email: String,
+        password_hash: Option<String>,
+        role: Option<UserRole>,
+    ) -> Result<UpsertOutcome, AppError>;
     async fn soft_delete(&self, id: i32) -> Result<bool, AppError>;
-    async fn list(&self, limit: i64, offset: i64, include_deleted: bool) -> Result<Vec<User>, AppError>;
+    async fn list(&self, query: &UserListQuery) -> Result<Vec<User>, AppError>;
     async fn verify_email(&self, id: i32) -> Result<User, AppError>;
+
+    // Substring search over email and profile name, ranked in memory.
+    // Backends with real text search (e.g. Postgres trigram indexes) should
+    // override this.
+    async fn search(&self, term: &str, limit: i64) -> Result<Vec<UserSearchHit>, AppError> {
+        // Rank the candidates before truncating, so the best of them are not
+        // cut off by the list order. Candidates are capped so a broad term
+        // can't load the whole table; past the cap, ranking is best effort.
+        let query = UserListQuery {
+            filter: UserFilter {
+                email_or_name_contains: Some(term.to_string()),
+                ..UserFilter::default()
+            },
+            sort: UserSortField::Email,
+            direction: SortDirection::Asc,
+            after: None,
+            limit: MAX_SEARCH_CANDIDATES,
+        };
+
+        let mut hits: Vec<UserSearchHit> = self.list(&query).await?
+            .into_iter()
+            .map(|user| {
+                let score = user_search::user_score(&user, term);
+                UserSearchHit::new(user, term, score)
+            })
+            .collect();
+
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(usize::try_from(limit).unwrap_or(0));
+        Ok(hits)
+    }
 } 
\ No newline at end of file
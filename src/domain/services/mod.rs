@@ -0,0 +1,3 @@
+pub mod password;
+pub mod registration;
+pub mod user_transfer;
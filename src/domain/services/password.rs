@@ -0,0 +1,37 @@
+use std::sync::Arc;
+use crate::{
+    domain::models::password_policy::PasswordPolicy,
+    infrastructure::{auth::password::hash_password, error::AppError},
+};
+
+// Checks new passwords against the configured policy and hashes them. Every
+// flow that sets a password (registration, bulk import) goes through here so
+// they all enforce the same rules.
+#[derive(Clone)]
+pub struct PasswordService {
+    policy: Arc<PasswordPolicy>,
+}
+
+impl PasswordService {
+    pub fn new(policy: Arc<PasswordPolicy>) -> Self {
+        Self { policy }
+    }
+
+    pub fn policy(&self) -> &Arc<PasswordPolicy> {
+        &self.policy
+    }
+
+    pub fn validate(&self, password: &str, email: &str) -> Result<(), AppError> {
+        self.policy.validate(password, Some(email))?;
+        Ok(())
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String, AppError> {
+        hash_password(password).map_err(|_| AppError::InternalServerError)
+    }
+
+    // Whether new hashes must be recorded for the reuse check
+    pub fn keeps_history(&self) -> bool {
+        self.policy.history_size > 0
+    }
+} 
\ No newline at end of file
@@ -0,0 +1,39 @@
+use crate::{
+    domain::{
+        models::user::{User, UserRole},
+        repositories::{
+            password_history_repository::PasswordHistoryRepository,
+            unit_of_work::{Transaction, UnitOfWork},
+            user_repository::UserRepository,
+        },
+        services::password::PasswordService,
+    },
+    infrastructure::error::AppError,
+};
+
+// Creates a user and seeds their password history in one transaction, so a
+// failure part-way never leaves a user whose first password can be reused.
+pub async fn register<U: UnitOfWork>(
+    uow: &U,
+    passwords: &PasswordService,
+    email: String,
+    password: String,
+    role: UserRole,
+) -> Result<User, AppError> {
+    passwords.validate(&password, &email)?;
+    let password_hash = passwords.hash(&password)?;
+    let record_history = passwords.keeps_history();
+
+    uow.transaction(move |tx| Box::pin(async move {
+        if tx.users().find_by_email(&email).await?.is_some() {
+            return Err(AppError::UserAlreadyExists);
+        }
+
+        let user = tx.users().create(email, password_hash, role).await?;
+        if record_history {
+            tx.password_history().record(user.id, &user.password).await?;
+        }
+        Ok(user)
+    }))
+    .await
+} 
\ No newline at end of file
@@ -0,0 +1,392 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use utoipa::ToSchema;
+use crate::{
+    domain::{
+        models::user::{UpsertOutcome, User, UserRole},
+        models::user_query::{SortDirection, UserCursor, UserFilter, UserListQuery, UserSortField},
+        repositories::user_repository::UserRepository,
+        services::password::PasswordService,
+    },
+    infrastructure::{
+        auth::password::is_bcrypt_hash,
+        error::{self, AppError},
+    },
+};
+
+// Rows hashed concurrently on the blocking pool
+const HASH_BATCH_SIZE: usize = 16;
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferFormat {
+    Csv,
+    Ndjson,
+}
+
+impl TransferFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TransferFormat::Csv => "text/csv",
+            TransferFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type.split(';').next()?.trim() {
+            "text/csv" => Some(TransferFormat::Csv),
+            "application/x-ndjson" | "application/jsonl" => Some(TransferFormat::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+// One input record. Either a plaintext `password` (validated and hashed) or a
+// bcrypt `password_hash` may be given; existing users may omit both.
+#[derive(Deserialize)]
+pub struct ImportRecord {
+    pub email: String,
+    pub password: Option<String>,
+    pub password_hash: Option<String>,
+    pub role: Option<UserRole>,
+}
+
+impl fmt::Debug for ImportRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = |value: &Option<String>| value.as_ref().map(|_| "[REDACTED]");
+        f.debug_struct("ImportRecord")
+            .field("email", &self.email)
+            .field("password", &redacted(&self.password))
+            .field("password_hash", &redacted(&self.password_hash))
+            .field("role", &self.role)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RowStatus {
+    Created,
+    Updated,
+    WouldCreate,
+    WouldUpdate,
+    Invalid,
+    Failed,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RowReport {
+    pub line: usize,
+    pub email: Option<String>,
+    pub status: RowStatus,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub failed: usize,
+    pub rows: Vec<RowReport>,
+}
+
+impl ImportReport {
+    fn push(&mut self, row: RowReport) {
+        match row.status {
+            RowStatus::Created | RowStatus::WouldCreate => self.created += 1,
+            RowStatus::Updated | RowStatus::WouldUpdate => self.updated += 1,
+            RowStatus::Invalid | RowStatus::Failed => self.failed += 1,
+        }
+        self.total += 1;
+        self.rows.push(row);
+    }
+}
+
+#[derive(Serialize)]
+struct ExportRecord<'a> {
+    id: i32,
+    email: &'a str,
+    role: &'a UserRole,
+    is_email_verified: bool,
+    created_at: chrono::NaiveDateTime,
+}
+
+pub const CSV_EXPORT_HEADER: &str = "id,email,role,is_email_verified,created_at\n";
+
+pub fn export_line(format: TransferFormat, user: &User) -> Result<String, AppError> {
+    let record = ExportRecord {
+        id: user.id,
+        email: &user.email,
+        role: &user.role,
+        is_email_verified: user.is_email_verified,
+        created_at: user.created_at,
+    };
+
+    match format {
+        TransferFormat::Ndjson => serde_json::to_string(&record)
+            .map(|line| line + "\n")
+            .map_err(|_| AppError::InternalServerError),
+        TransferFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(Vec::new());
+            writer.serialize(&record).map_err(|_| AppError::InternalServerError)?;
+            writer
+                .into_inner()
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .ok_or(AppError::InternalServerError)
+        }
+    }
+}
+
+// Streams every non-deleted user to `tx` page by page, stopping early if the
+// receiver goes away.
+pub async fn export_users<T: UserRepository>(
+    repo: &T,
+    format: TransferFormat,
+    tx: mpsc::Sender<Result<String, AppError>>,
+) {
+    if format == TransferFormat::Csv && tx.send(Ok(CSV_EXPORT_HEADER.to_string())).await.is_err() {
+        return;
+    }
+
+    let mut query = UserListQuery {
+        filter: UserFilter::default(),
+        sort: UserSortField::CreatedAt,
+        direction: SortDirection::Asc,
+        after: None,
+        limit: EXPORT_PAGE_SIZE,
+    };
+
+    loop {
+        let page = match repo.list(&query).await {
+            Ok(page) => page,
+            Err(e) => {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+        };
+
+        let chunk: Result<String, AppError> = page.iter().map(|user| export_line(format, user)).collect();
+        if tx.send(chunk).await.is_err() || (page.len() as i64) < EXPORT_PAGE_SIZE {
+            return;
+        }
+
+        query.after = page.last().map(|user| UserCursor::for_user(UserSortField::CreatedAt, user));
+    }
+}
+
+// Parses `input`, returning each record with its 1-based source line, or the
+// parse error for that line.
+pub fn parse_records(
+    format: TransferFormat,
+    input: &str,
+) -> Vec<(usize, Result<ImportRecord, String>)> {
+    match format {
+        TransferFormat::Ndjson => input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| {
+                (idx + 1, serde_json::from_str(line).map_err(|e| e.to_string()))
+            })
+            .collect(),
+        TransferFormat::Csv => csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(input.as_bytes())
+            .deserialize::<ImportRecord>()
+            .enumerate()
+            .map(|(idx, record)| {
+                let line = record
+                    .as_ref()
+                    .err()
+                    .and_then(|e| e.position())
+                    .map(|pos| pos.line() as usize)
+                    .unwrap_or(idx + 2); // header is line 1
+                (line, record.map_err(|e| e.to_string()))
+            })
+            .collect(),
+    }
+}
+
+fn validate_record(passwords: &PasswordService, record: &ImportRecord) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    if !validator::validate_email(&record.email) {
+        errors.push("email: Invalid email format".to_string());
+    }
+
+    match (&record.password, &record.password_hash) {
+        (Some(_), Some(_)) => {
+            errors.push("password: Provide either password or password_hash, not both".to_string());
+        }
+        (Some(password), None) => match passwords.validate(password, &record.email) {
+            Ok(()) => {}
+            Err(AppError::ValidationError(e)) => {
+                errors.extend(error::field_errors(&e).into_iter().map(|err| format!(
+                    "{}: {}",
+                    err.field,
+                    err.message.unwrap_or(err.code),
+                )));
+            }
+            Err(e) => errors.push(format!("password: {}", e)),
+        },
+        (None, Some(password_hash)) => {
+            if !is_bcrypt_hash(password_hash) {
+                errors.push("password_hash: Must be a bcrypt hash".to_string());
+            }
+        }
+        (None, None) => {}
+    }
+
+    errors
+}
+
+pub async fn import_users<T: UserRepository>(
+    repo: &T,
+    passwords: &PasswordService,
+    format: TransferFormat,
+    input: &str,
+    dry_run: bool,
+) -> Result<ImportReport, AppError> {
+    let mut report = ImportReport {
+        dry_run,
+        ..ImportReport::default()
+    };
+
+    let mut valid = Vec::new();
+    for (line, record) in parse_records(format, input) {
+        match record {
+            Ok(record) => {
+                let errors = validate_record(passwords, &record);
+                if errors.is_empty() {
+                    valid.push((line, record));
+                } else {
+                    report.push(RowReport {
+                        line,
+                        email: Some(record.email),
+                        status: RowStatus::Invalid,
+                        errors,
+                    });
+                }
+            }
+            Err(e) => report.push(RowReport {
+                line,
+                email: None,
+                status: RowStatus::Invalid,
+                errors: vec![e],
+            }),
+        }
+    }
+
+    for batch in valid.chunks_mut(HASH_BATCH_SIZE) {
+        // Skip hashing entirely on dry runs; it is the expensive part
+        let hashes = if dry_run {
+            batch.iter().map(|(_, record)| record.password_hash.clone()).collect()
+        } else {
+            hash_batch(passwords, batch).await?
+        };
+
+        for ((line, record), password_hash) in batch.iter_mut().zip(hashes) {
+            let row = if dry_run {
+                dry_run_row(repo, *line, record).await
+            } else {
+                let email = std::mem::take(&mut record.email);
+                match repo.upsert_by_email(email.clone(), password_hash, record.role).await {
+                    Ok(outcome) => RowReport {
+                        line: *line,
+                        email: Some(email),
+                        status: match outcome {
+                            UpsertOutcome::Created => RowStatus::Created,
+                            UpsertOutcome::Updated => RowStatus::Updated,
+                        },
+                        errors: Vec::new(),
+                    },
+                    Err(e) => RowReport {
+                        line: *line,
+                        email: Some(email),
+                        status: RowStatus::Failed,
+                        errors: vec![e.to_string()],
+                    },
+                }
+            };
+            report.push(row);
+        }
+    }
+
+    report.rows.sort_by_key(|row| row.line);
+    Ok(report)
+}
+
+async fn dry_run_row<T: UserRepository>(repo: &T, line: usize, record: &ImportRecord) -> RowReport {
+    let (status, errors) = match repo.find_by_email(&record.email).await {
+        Ok(Some(_)) => (RowStatus::WouldUpdate, Vec::new()),
+        Ok(None) if record.password.is_none() && record.password_hash.is_none() => (
+            RowStatus::Invalid,
+            vec!["password: Required for new users".to_string()],
+        ),
+        Ok(None) => (RowStatus::WouldCreate, Vec::new()),
+        Err(e) => (RowStatus::Failed, vec![e.to_string()]),
+    };
+
+    RowReport {
+        line,
+        email: Some(record.email.clone()),
+        status,
+        errors,
+    }
+}
+
+async fn hash_batch(passwords: &PasswordService, batch: &[(usize, ImportRecord)]) -> Result<Vec<Option<String>>, AppError> {
+    let handles: Vec<_> = batch
+        .iter()
+        .map(|(_, record)| {
+            let password = record.password.clone();
+            let password_hash = record.password_hash.clone();
+            let passwords = passwords.clone();
+            // Keep the caller's span as parent on the blocking thread
+            let span = tracing::Span::current();
+            tokio::task::spawn_blocking(move || span.in_scope(|| match password {
+                Some(password) => passwords.hash(&password).map(Some),
+                None => Ok(password_hash),
+            }))
+        })
+        .collect();
+
+    let mut hashes = Vec::with_capacity(handles.len());
+    for handle in handles {
+        hashes.push(handle.await.map_err(|_| AppError::InternalServerError)??);
+    }
+    Ok(hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::domain::models::password_policy::PasswordPolicy;
+
+    fn record(password_hash: &str) -> ImportRecord {
+        ImportRecord {
+            email: "a@example.com".to_string(),
+            password: None,
+            password_hash: Some(password_hash.to_string()),
+            role: None,
+        }
+    }
+
+    #[test]
+    fn password_hash_must_be_a_full_bcrypt_hash() {
+        let passwords = PasswordService::new(Arc::new(PasswordPolicy::default()));
+        let valid = bcrypt::hash("Secret1!", 4).unwrap();
+
+        assert!(validate_record(&passwords, &record(&valid)).is_empty());
+        assert!(!validate_record(&passwords, &record("$2b$12$tooshort")).is_empty());
+        assert!(!validate_record(&passwords, &record("$2-not-a-hash")).is_empty());
+    }
+} 
\ No newline at end of file
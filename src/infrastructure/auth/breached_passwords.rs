@@ -0,0 +1,128 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Mutex;
+use sha1::{Digest, Sha1};
+use crate::domain::models::password_policy::BreachedPasswordCheck;
+
+const HASH_LEN: usize = 40;
+// Longer than any `HASH:count` line, so one read always holds a full line
+const WINDOW: u64 = 256;
+
+// Local copy of a breached-password corpus in the "Pwned Passwords" format:
+// one upper-case SHA-1 hash per line, optionally followed by `:count`, sorted
+// by hash as in the "ordered by hash" download. The file is never loaded;
+// each lookup binary-searches it with a few small reads.
+pub struct BreachedPasswordDataset {
+    file: Mutex<File>,
+    len: u64,
+}
+
+impl BreachedPasswordDataset {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let dataset = Self { file: Mutex::new(file), len };
+
+        // Catches an unsorted or foreign file early; a full scan would take
+        // minutes on the real corpus
+        let first = dataset.line_at(0)?;
+        if first.is_some_and(|hash| !is_hash(&hash)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "expected upper-case SHA-1 hashes sorted by hash",
+            ));
+        }
+
+        tracing::info!("Using breached password hashes from {} ({} bytes)", path.display(), len);
+        Ok(dataset)
+    }
+
+    // Hash on the first line starting at or after `offset`
+    fn line_at(&self, offset: u64) -> io::Result<Option<String>> {
+        // Starting one byte early finds a line that begins exactly at `offset`
+        let from = offset.saturating_sub(1);
+        let mut buf = Vec::with_capacity(WINDOW as usize);
+        {
+            let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+            file.seek(SeekFrom::Start(from))?;
+            (&mut *file).take(WINDOW).read_to_end(&mut buf)?;
+        }
+
+        let start = if offset == 0 {
+            0
+        } else {
+            match buf.iter().position(|&b| b == b'\n') {
+                Some(newline) => newline + 1,
+                None => return Ok(None),
+            }
+        };
+        let line = &buf[start..];
+        if line.is_empty() {
+            return Ok(None);
+        }
+        let end = line.iter().position(|&b| b == b'\n' || b == b':' || b == b'\r').unwrap_or(line.len());
+        Ok(Some(String::from_utf8_lossy(&line[..end]).trim().to_uppercase()))
+    }
+
+    fn contains(&self, target: &str) -> io::Result<bool> {
+        // Smallest offset whose next line sorts at or after `target`
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.line_at(mid)? {
+                Some(hash) if hash.as_str() < target => low = mid + 1,
+                _ => high = mid,
+            }
+        }
+        Ok(self.line_at(low)?.is_some_and(|hash| hash == target))
+    }
+}
+
+fn is_hash(value: &str) -> bool {
+    value.len() == HASH_LEN && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+impl BreachedPasswordCheck for BreachedPasswordDataset {
+    fn is_breached(&self, password: &str) -> bool {
+        let digest = Sha1::digest(password.as_bytes());
+        let hash: String = digest.iter().map(|b| format!("{:02X}", b)).collect();
+
+        // Failing open: an unreadable corpus shouldn't block every sign-up
+        self.contains(&hash).unwrap_or_else(|e| {
+            tracing::error!("Breached password lookup failed: {}", e);
+            false
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use super::*;
+
+    fn sha1_hex(password: &str) -> String {
+        Sha1::digest(password.as_bytes()).iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    #[test]
+    fn finds_hashes_in_a_sorted_corpus() {
+        let mut hashes: Vec<String> = (0..500).map(|i| sha1_hex(&format!("password{}", i))).collect();
+        hashes.sort();
+
+        let path = std::env::temp_dir().join(format!("breached-{}.txt", uuid::Uuid::new_v4()));
+        let mut file = File::create(&path).unwrap();
+        for (i, hash) in hashes.iter().enumerate() {
+            writeln!(file, "{}:{}", hash, i + 1).unwrap();
+        }
+        drop(file);
+
+        let dataset = BreachedPasswordDataset::load(&path).unwrap();
+        assert!(dataset.is_breached("password0"));
+        assert!(dataset.is_breached("password499"));
+        assert!((0..500).all(|i| dataset.is_breached(&format!("password{}", i))));
+        assert!(!dataset.is_breached("Correct-Horse-Battery-Staple-1"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+} 
\ No newline at end of file
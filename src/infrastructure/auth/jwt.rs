@@ -1,7 +1,15 @@
-use crate::domain::models::user::Claims;
+use std::sync::Arc;
+use arc_swap::ArcSwap;
+use crate::domain::models::user::{Claims, EmailVerificationClaims, UserRole};
+use crate::infrastructure::config::app::AuthConfig;
+use crate::infrastructure::metrics::TOKENS_ISSUED_TOTAL;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use std::env;
 use thiserror::Error;
+use tracing::instrument;
+
+// Verification links stay valid for a day
+const EMAIL_VERIFICATION_TTL_SECS: usize = 24 * 3600;
+const EMAIL_VERIFICATION_PURPOSE: &str = "email_verification";
 
 #[derive(Error, Debug)]
 pub enum JwtError {
@@ -11,36 +19,101 @@ pub enum JwtError {
     TokenVerification,
 }
 
+// Signing key and every key accepted when verifying, swapped together on
+// config reload
+struct JwtKeys {
+    encoding: EncodingKey,
+    // `jwt_secret` first, then `jwt_verification_secrets`
+    verification: Vec<DecodingKey>,
+}
+
+impl JwtKeys {
+    fn from_config(config: &AuthConfig) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(config.jwt_secret.expose().as_bytes()),
+            verification: std::iter::once(&config.jwt_secret)
+                .chain(config.jwt_verification_secrets.iter())
+                .map(|secret| DecodingKey::from_secret(secret.expose().as_bytes()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct JwtService {
-    encoding_key: EncodingKey,
-    decoding_key: DecodingKey,
+    // Shared by every clone
+    keys: Arc<ArcSwap<JwtKeys>>,
+    token_ttl_secs: usize,
 }
 
 impl JwtService {
-    pub fn new() -> Self {
-        let secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+    pub fn new(config: &AuthConfig) -> Self {
         Self {
-            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
-            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            keys: Arc::new(ArcSwap::from_pointee(JwtKeys::from_config(config))),
+            token_ttl_secs: config.token_ttl_secs as usize,
         }
     }
 
-    pub fn generate_token(&self, user_id: i32) -> Result<String, JwtError> {
+    // New tokens are signed with the reloaded `jwt_secret`; tokens signed
+    // with a rotated-out secret verify as long as it is still listed in
+    // `jwt_verification_secrets`
+    pub fn reload_keys(&self, config: &AuthConfig) {
+        self.keys.store(Arc::new(JwtKeys::from_config(config)));
+    }
+
+    #[instrument(name = "jwt.generate", skip_all, fields(user.id = user_id))]
+    pub fn generate_token(&self, user_id: i32, role: UserRole) -> Result<String, JwtError> {
         let now = chrono::Utc::now().timestamp() as usize;
         let claims = Claims {
             sub: user_id,
-            exp: now + 24 * 3600, // 24 hours from now
+            role,
+            exp: now + self.token_ttl_secs,
             iat: now,
         };
 
-        encode(&Header::default(), &claims, &self.encoding_key)
-            .map_err(|_| JwtError::TokenCreation)
+        let token = encode(&Header::default(), &claims, &self.keys.load().encoding)
+            .map_err(|_| JwtError::TokenCreation)?;
+        TOKENS_ISSUED_TOTAL.inc();
+        Ok(token)
     }
 
+    #[instrument(name = "jwt.verify", skip_all)]
     pub fn verify_token(&self, token: &str) -> Result<Claims, JwtError> {
         let validation = Validation::default();
-        decode::<Claims>(token, &self.decoding_key, &validation)
+        self.keys
+            .load()
+            .verification
+            .iter()
+            .find_map(|key| decode::<Claims>(token, key, &validation).ok())
             .map(|data| data.claims)
-            .map_err(|_| JwtError::TokenVerification)
+            .ok_or(JwtError::TokenVerification)
+    }
+
+    #[instrument(name = "jwt.generate_email_verification", skip_all, fields(user.id = user_id))]
+    pub fn generate_email_verification_token(&self, user_id: i32) -> Result<String, JwtError> {
+        let now = chrono::Utc::now().timestamp() as usize;
+        let claims = EmailVerificationClaims {
+            sub: user_id,
+            purpose: EMAIL_VERIFICATION_PURPOSE.to_string(),
+            exp: now + EMAIL_VERIFICATION_TTL_SECS,
+            iat: now,
+        };
+
+        encode(&Header::default(), &claims, &self.keys.load().encoding)
+            .map_err(|_| JwtError::TokenCreation)
+    }
+
+    // Returns the id of the user whose email the token verifies
+    #[instrument(name = "jwt.verify_email_verification", skip_all)]
+    pub fn verify_email_verification_token(&self, token: &str) -> Result<i32, JwtError> {
+        let validation = Validation::default();
+        self.keys
+            .load()
+            .verification
+            .iter()
+            .find_map(|key| decode::<EmailVerificationClaims>(token, key, &validation).ok())
+            .filter(|data| data.claims.purpose == EMAIL_VERIFICATION_PURPOSE)
+            .map(|data| data.claims.sub)
+            .ok_or(JwtError::TokenVerification)
     }
 } 
\ No newline at end of file
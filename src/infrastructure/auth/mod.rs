@@ -0,0 +1,3 @@
+pub mod breached_passwords;
+pub mod jwt;
+pub mod password;
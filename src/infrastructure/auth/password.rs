@@ -0,0 +1,19 @@
+use bcrypt::{hash, verify, BcryptError, HashParts, DEFAULT_COST};
+use tracing::instrument;
+
+// bcrypt is deliberately slow, so each call gets its own span
+
+#[instrument(name = "bcrypt.hash", skip_all)]
+pub fn hash_password(password: &str) -> Result<String, BcryptError> {
+    hash(password.as_bytes(), DEFAULT_COST)
+}
+
+#[instrument(name = "bcrypt.verify", skip_all)]
+pub fn verify_password(password: &str, password_hash: &str) -> Result<bool, BcryptError> {
+    verify(password.as_bytes(), password_hash)
+}
+
+// A complete bcrypt hash (`$2b$12$` + salt + digest), as accepted by `verify_password`
+pub fn is_bcrypt_hash(value: &str) -> bool {
+    value.parse::<HashParts>().is_ok()
+} 
\ No newline at end of file
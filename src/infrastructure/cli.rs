@@ -0,0 +1,230 @@
+use std::io::Write;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use crate::{
+    application::openapi,
+    domain::services::{
+        password::PasswordService,
+        user_transfer::{self, TransferFormat},
+    },
+    infrastructure::{
+        config::{app::AppConfig, database},
+        repositories::user_repository::DieselUserRepository,
+        secrets::encrypted,
+    },
+};
+
+const USAGE: &str = "Usage:
+  rust-clean-architecture config check
+  rust-clean-architecture openapi print|write|check
+  rust-clean-architecture secrets keygen
+  rust-clean-architecture secrets encrypt <secrets.json>   (key from SECRETS_KEY)
+  rust-clean-architecture users import <file> [--format csv|ndjson] [--dry-run]
+  rust-clean-architecture users export [--format csv|ndjson]";
+
+// Runs a one-off command and returns the process exit code
+pub async fn run(args: &[String]) -> i32 {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["config", "check"] => return check_config(),
+        ["openapi", command] => return openapi_spec(command),
+        ["secrets", "keygen"] => {
+            println!("{}", encrypted::generate_key());
+            return 0;
+        }
+        ["secrets", "encrypt", file] => return encrypt_secrets(file),
+        _ => {}
+    }
+    if args.first() != Some(&"users") {
+        eprintln!("{}", USAGE);
+        return 2;
+    }
+
+    let config = match AppConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprint!("{}", e);
+            return 1;
+        }
+    };
+    let policy = match config.auth.password_policy() {
+        Ok(policy) => Arc::new(policy),
+        Err(e) => {
+            eprint!("{}", e);
+            return 1;
+        }
+    };
+    let passwords = PasswordService::new(policy.clone());
+    let repo = DieselUserRepository::new(database::establish_connection_pool(&config.database))
+        .with_policy(policy);
+
+    match args.as_slice() {
+        ["users", "import", file, flags @ ..] => import_users(&repo, &passwords, file, flags).await,
+        ["users", "export", flags @ ..] => export_users(&repo, flags).await,
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    }
+}
+
+fn check_config() -> i32 {
+    match AppConfig::load() {
+        Ok(config) => {
+            println!("Configuration OK (profile: {})", config.profile.as_str());
+            0
+        }
+        Err(e) => {
+            eprint!("{}", e);
+            1
+        }
+    }
+}
+
+// `write` refreshes the committed spec after an intentional API change;
+// `check` is run in CI so an unintended one fails the build
+fn openapi_spec(command: &str) -> i32 {
+    let spec = openapi::to_json() + "\n";
+    match command {
+        "print" => {
+            print!("{}", spec);
+            0
+        }
+        "write" => match std::fs::write(openapi::SPEC_FILE, &spec) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Failed to write {}: {}", openapi::SPEC_FILE, e);
+                1
+            }
+        },
+        "check" => match std::fs::read_to_string(openapi::SPEC_FILE) {
+            Ok(committed) if committed == spec => {
+                println!("{} is up to date", openapi::SPEC_FILE);
+                0
+            }
+            Ok(_) => {
+                eprintln!(
+                    "{} is out of date; review the API change and run `openapi write`",
+                    openapi::SPEC_FILE
+                );
+                1
+            }
+            Err(e) => {
+                eprintln!("Failed to read {} ({}); create it with `openapi write`", openapi::SPEC_FILE, e);
+                1
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    }
+}
+
+// Encrypts a JSON object of name -> value into the `SECRETS_FILE` format,
+// written to stdout
+fn encrypt_secrets(file: &str) -> i32 {
+    let Ok(key) = std::env::var("SECRETS_KEY") else {
+        eprintln!("SECRETS_KEY must be set (see `secrets keygen`)");
+        return 2;
+    };
+    let plaintext = match std::fs::read(file) {
+        Ok(plaintext) => zeroize::Zeroizing::new(plaintext),
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", file, e);
+            return 1;
+        }
+    };
+    if serde_json::from_slice::<std::collections::HashMap<String, String>>(&plaintext).is_err() {
+        eprintln!("{} must contain a JSON object of string values", file);
+        return 1;
+    }
+
+    match encrypted::encrypt(&key, &plaintext) {
+        Ok(encoded) => {
+            println!("{}", encoded);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn parse_format(flags: &[&str]) -> Result<Option<TransferFormat>, String> {
+    match flags.iter().position(|flag| *flag == "--format") {
+        Some(idx) => match flags.get(idx + 1) {
+            Some(&"csv") => Ok(Some(TransferFormat::Csv)),
+            Some(&"ndjson") => Ok(Some(TransferFormat::Ndjson)),
+            other => Err(format!("Unknown format: {}", other.unwrap_or(&""))),
+        },
+        None => Ok(None),
+    }
+}
+
+async fn import_users(repo: &DieselUserRepository, passwords: &PasswordService, file: &str, flags: &[&str]) -> i32 {
+    let format = match parse_format(flags) {
+        Ok(Some(format)) => format,
+        Ok(None) if file.ends_with(".csv") => TransferFormat::Csv,
+        Ok(None) => TransferFormat::Ndjson,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return 2;
+        }
+    };
+    let dry_run = flags.contains(&"--dry-run");
+
+    let input = match tokio::fs::read_to_string(file).await {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", file, e);
+            return 1;
+        }
+    };
+
+    match user_transfer::import_users(repo, passwords, format, &input, dry_run).await {
+        Ok(report) => {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+            if report.failed > 0 { 1 } else { 0 }
+        }
+        Err(e) => {
+            eprintln!("Import failed: {}", e);
+            1
+        }
+    }
+}
+
+async fn export_users(repo: &DieselUserRepository, flags: &[&str]) -> i32 {
+    let format = match parse_format(flags) {
+        Ok(format) => format.unwrap_or(TransferFormat::Ndjson),
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    let (tx, mut rx) = mpsc::channel(4);
+    let producer = user_transfer::export_users(repo, format, tx);
+    let consumer = async {
+        let mut stdout = std::io::stdout().lock();
+        while let Some(chunk) = rx.recv().await {
+            match chunk {
+                Ok(chunk) => {
+                    if stdout.write_all(chunk.as_bytes()).is_err() {
+                        return 1;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Export failed: {}", e);
+                    return 1;
+                }
+            }
+        }
+        0
+    };
+
+    let ((), code) = tokio::join!(producer, consumer);
+    code
+} 
\ No newline at end of file
@@ -1,29 +1,895 @@
-use std::env;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::{
+    domain::models::password_policy::PasswordPolicy,
+    infrastructure::{
+        auth::breached_passwords::BreachedPasswordDataset,
+        config::loader,
+        secrets::secret::Secret,
+    },
+};
 
-#[derive(Clone)]
-pub struct AppConfig {
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    Dev,
+    Test,
+    Prod,
+}
+
+impl Profile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Profile::Dev => "dev",
+            Profile::Test => "test",
+            Profile::Prod => "prod",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    // `0.0.0.0` to listen on every interface, e.g. inside a container
+    pub host: IpAddr,
     pub port: u16,
-    pub rate_limit_requests: u64,
-    pub rate_limit_duration: u64,
+    // Negotiated over ALPN with TLS, prior knowledge (h2c) without
+    pub http2: bool,
+    pub tls: Option<TlsConfig>,
+    // How long in-flight requests and background work may take to finish
+    // after SIGTERM before they are cut off
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 3000,
+            http2: true,
+            tls: None,
+            shutdown_timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuth {
+    #[default]
+    None,
+    // Certificates are verified when presented but not required
+    Optional,
+    Required,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    // PEM files, re-read when they change
+    pub cert_file: String,
+    pub key_file: String,
+    // CA bundle that client certificates must chain to
+    pub client_ca_file: Option<String>,
+    #[serde(default)]
+    pub client_auth: ClientAuth,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: Secret,
+    pub replica_urls: Vec<Secret>,
+    // Replaces the password in `url` and `replica_urls`, so the URLs themselves
+    // can live in plain config
+    pub password: Option<Secret>,
+    pub replica_health_check_interval: u64,
+    // How long a read waits for a replica connection before falling back to
+    // the primary; kept short so a dead replica costs little
+    pub replica_connection_timeout_ms: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: Secret::default(),
+            replica_urls: Vec::new(),
+            password: None,
+            replica_health_check_interval: 10,
+            replica_connection_timeout_ms: 1500,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn primary_url(&self) -> Secret {
+        self.with_password(&self.url)
+    }
+
+    pub fn replica_urls(&self) -> Vec<Secret> {
+        self.replica_urls.iter().map(|url| self.with_password(url)).collect()
+    }
+
+    fn with_password(&self, url: &Secret) -> Secret {
+        let Some(password) = &self.password else {
+            return url.clone();
+        };
+        let url = url.expose();
+        let Some((scheme, rest)) = url.split_once("://") else {
+            return Secret::new(url.to_string());
+        };
+        let Some((userinfo, host)) = rest.split_once('@') else {
+            return Secret::new(url.to_string());
+        };
+        let user = userinfo.split(':').next().unwrap_or_default();
+
+        Secret::new(format!("{}://{}:{}@{}", scheme, user, percent_encode(password.expose()), host))
+    }
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordConfig {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+    pub disallow_email: bool,
+    pub history_size: i64,
+    pub breached_passwords_file: Option<String>,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        let policy = PasswordPolicy::default();
+        Self {
+            min_length: policy.min_length,
+            max_length: policy.max_length,
+            require_uppercase: policy.require_uppercase,
+            require_lowercase: policy.require_lowercase,
+            require_digit: policy.require_digit,
+            require_special: policy.require_special,
+            disallow_email: policy.disallow_email,
+            history_size: policy.history_size,
+            breached_passwords_file: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub jwt_secret: Secret,
+    // Previous secrets still accepted when verifying tokens, for key rotation
+    pub jwt_verification_secrets: Vec<Secret>,
+    pub token_ttl_secs: u64,
+    pub password: PasswordConfig,
+    // Refuse to log in users who have not verified their email address
+    pub require_verified_email: bool,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: Secret::default(),
+            jwt_verification_secrets: Vec::new(),
+            token_ttl_secs: 24 * 3600,
+            password: PasswordConfig::default(),
+            require_verified_email: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    // Default policy: a bucket of `requests` tokens per client, refilled
+    // evenly over `duration_secs`
+    pub requests: u64,
+    pub duration_secs: u64,
+    // Clients sending one of `api_keys` in this header are limited per key
+    // rather than per user or IP; unknown keys are ignored
+    pub api_key_header: String,
+    pub api_keys: Vec<Secret>,
+    pub backend: RateLimitBackend,
+    // Let requests through when the backend fails, rather than answer 503
+    pub fail_open: bool,
+    // Stricter or looser limits for routes under a path prefix; the longest
+    // matching prefix wins
+    pub policies: Vec<RateLimitPolicy>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests: 100,
+            duration_secs: 60,
+            api_key_header: "x-api-key".to_string(),
+            api_keys: Vec::new(),
+            backend: RateLimitBackend::Memory,
+            fail_open: true,
+            policies: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    // Per instance
+    Memory,
+    // Shared by every instance using the same database
+    Postgres,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitPolicy {
+    pub path_prefix: String,
+    pub requests: u64,
+    pub duration_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    // Exact origins (`https://app.example.com`) or subdomain patterns
+    // (`https://*.example.com`); empty denies every cross-origin request
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    // `*` allows any request header, except with credentials
+    pub allowed_headers: Vec<String>,
+    // Response headers readable by browser scripts
+    pub exposed_headers: Vec<String>,
+    pub allow_credentials: bool,
+    // How long browsers may cache a preflight response
+    pub max_age_secs: u64,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: strings(&["GET", "POST", "PUT", "PATCH", "DELETE"]),
+            allowed_headers: strings(&["authorization", "content-type", "accept-language", "if-match", "x-request-id", "idempotency-key"]),
+            exposed_headers: strings(&[
+                "etag",
+                "retry-after",
+                "x-request-id",
+                "ratelimit-limit",
+                "ratelimit-remaining",
+                "ratelimit-reset",
+                "ratelimit-policy",
+                "traceparent",
+                "deprecation",
+                "sunset",
+                "link",
+                "idempotent-replayed",
+            ]),
+            allow_credentials: false,
+            max_age_secs: 600,
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins.iter().any(|allowed| origin_matches(allowed, origin))
+    }
+}
+
+// `https://*.example.com` matches any subdomain at any depth, but not
+// `https://example.com` itself
+fn origin_matches(pattern: &str, origin: &str) -> bool {
+    if pattern.eq_ignore_ascii_case(origin) {
+        return true;
+    }
+
+    let Some((scheme, host)) = pattern.split_once("://*.") else {
+        return false;
+    };
+    let Some(rest) = origin
+        .get(..scheme.len() + 3)
+        .filter(|prefix| prefix.eq_ignore_ascii_case(&format!("{}://", scheme)))
+        .map(|prefix| &origin[prefix.len()..])
+    else {
+        return false;
+    };
+
+    let suffix = format!(".{}", host.to_ascii_lowercase());
+    let rest = rest.to_ascii_lowercase();
+    rest.len() > suffix.len() && rest.ends_with(&suffix)
+}
+
+fn valid_origin_pattern(pattern: &str) -> bool {
+    let Some((scheme, host)) = pattern.split_once("://") else {
+        return false;
+    };
+    let host = host.strip_prefix("*.").unwrap_or(host);
+    (scheme == "http" || scheme == "https")
+        && !host.is_empty()
+        && !host.contains(['/', '*', '?', '#'])
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    // `EnvFilter` directive, e.g. `info` or `info,rust_clean_architecture=debug`
+    pub level: String,
+    pub format: LogFormat,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::Text,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    // One JSON object per line, for log shippers
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailConfig {
+    pub smtp_url: Option<Secret>,
+    pub from_address: Option<String>,
+    // Page that completes email verification; the token is appended as
+    // `?token=...`, e.g. `https://app.example.com/verify-email`
+    pub verification_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    // `/metrics` is served on its own listener so it is never exposed on the
+    // public port
+    pub host: IpAddr,
+    pub port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            host: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 9100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    // OTLP/gRPC collector, e.g. `http://localhost:4317`; traces are only
+    // exported when set
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+    // Fraction of new traces sampled; incoming `traceparent` decisions win
+    pub sample_ratio: f64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: "rust-clean-architecture".to_string(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecurityConfig {
+    // 0 disables `Strict-Transport-Security`
+    pub hsts_max_age_secs: u64,
+    pub content_security_policy: String,
+    pub referrer_policy: String,
+    pub frame_options: String,
+    // Defaults for every route, overridden per path prefix by `routes`
+    pub max_body_bytes: u64,
+    pub request_timeout_secs: u64,
+    pub content_types: Vec<String>,
+    pub routes: Vec<RouteSecurity>,
+    // Proxies (IPs or CIDRs) whose `X-Forwarded-For` is believed
+    pub trusted_proxies: Vec<String>,
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            hsts_max_age_secs: 31_536_000,
+            content_security_policy: "default-src 'none'; frame-ancestors 'none'".to_string(),
+            referrer_policy: "no-referrer".to_string(),
+            frame_options: "DENY".to_string(),
+            max_body_bytes: 1024 * 1024,
+            request_timeout_secs: 30,
+            content_types: vec!["application/json".to_string()],
+            routes: Vec::new(),
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteSecurity {
+    pub path_prefix: String,
+    pub max_body_bytes: Option<u64>,
+    pub request_timeout_secs: Option<u64>,
+    // Accepted request body media types
+    pub content_types: Option<Vec<String>>,
+    pub content_security_policy: Option<String>,
+}
+
+impl SecurityConfig {
+    // Longest matching `path_prefix` wins
+    pub fn route(&self, path: &str) -> Option<&RouteSecurity> {
+        self.routes
+            .iter()
+            .filter(|route| path.starts_with(&route.path_prefix))
+            .max_by_key(|route| route.path_prefix.len())
+    }
+
+    pub fn largest_body_limit(&self) -> u64 {
+        self.routes
+            .iter()
+            .filter_map(|route| route.max_body_bytes)
+            .fold(self.max_body_bytes, u64::max)
+    }
+}
+
+// Mounted as `/api/<version>`, oldest first
+pub const API_VERSIONS: &[&str] = &["v1", "v2"];
+
+// The pre-versioning paths (`/users`, `/auth/login`, ...), served as aliases
+// of v1. They are always deprecated; `[[api.versions]]` may set their dates.
+pub const UNVERSIONED: &str = "unversioned";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    // Lifecycle of each API version; versions not listed are current, except
+    // `unversioned`, which falls back to `unversioned_lifecycle`
+    pub versions: Vec<ApiVersionConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiVersionConfig {
+    // e.g. `v1`, as mounted under `/api/`
+    pub version: String,
+    // RFC 3339 timestamps, sent as the `Deprecation` and `Sunset` headers
+    pub deprecated_at: Option<String>,
+    pub sunset_at: Option<String>,
+    // Migration guide, sent as `Link: <...>; rel="deprecation"`
+    pub deprecation_link: Option<String>,
+}
+
+impl ApiConfig {
+    pub fn version(&self, version: &str) -> Option<&ApiVersionConfig> {
+        self.versions.iter().find(|v| v.version == version)
+    }
+
+    // Deprecated since versioning was introduced, removed six months later
+    // unless configured otherwise
+    pub fn unversioned_lifecycle(&self) -> ApiVersionConfig {
+        self.version(UNVERSIONED).cloned().unwrap_or_else(|| ApiVersionConfig {
+            version: UNVERSIONED.to_string(),
+            deprecated_at: Some("2026-10-18T00:00:00Z".to_string()),
+            sunset_at: Some("2027-04-18T00:00:00Z".to_string()),
+            deprecation_link: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdempotencyConfig {
+    // How long the first response to an `Idempotency-Key` is replayed
+    pub ttl_secs: u64,
+    pub backend: IdempotencyBackend,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 24 * 3600,
+            backend: IdempotencyBackend::Memory,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdempotencyBackend {
+    // Per instance; a retry landing on another instance runs again
+    Memory,
+    // Shared by every instance using the same database
+    Postgres,
+}
+
+// Top-level sections of the config file, one per `AppConfig` field
+pub const SECTIONS: &[&str] = &[
+    "server", "database", "auth", "rate_limit", "cors", "logging", "mail",
+    "metrics", "telemetry", "security", "api", "idempotency",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppConfig {
+    pub profile: Profile,
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub rate_limit: RateLimitConfig,
+    pub cors: CorsConfig,
+    pub logging: LoggingConfig,
+    pub mail: MailConfig,
+    pub metrics: MetricsConfig,
+    pub telemetry: TelemetryConfig,
+    pub security: SecurityConfig,
+    pub api: ApiConfig,
+    pub idempotency: IdempotencyConfig,
+}
+
+// Every problem found while loading configuration, reported together
+#[derive(Debug)]
+pub struct ConfigError {
+    pub errors: Vec<String>,
 }
 
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Invalid configuration ({} errors):", self.errors.len())?;
+        for error in &self.errors {
+            writeln!(f, "  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 impl AppConfig {
-    pub fn from_env() -> Self {
-        Self {
-            port: env::var("PORT")
-                .unwrap_or_else(|_| "3000".to_string())
-                .parse()
-                .expect("PORT must be a number"),
-            
-            rate_limit_requests: env::var("RATE_LIMIT_REQUEST")
-                .unwrap_or_else(|_| "100".to_string())
-                .parse()
-                .expect("RATE_LIMIT_REQUEST must be a number"),
-                
-            rate_limit_duration: env::var("RATE_LIMIT_DURATION")
-                .unwrap_or_else(|_| "60".to_string())
-                .parse()
-                .expect("RATE_LIMIT_DURATION must be a number"),
-        }
-    }
-} 
\ No newline at end of file
+    // Layers built-in defaults, `config/default.*`, `config/<profile>.*`,
+    // `APP_CONFIG_FILE` and environment variables, resolves secret references,
+    // then validates the result.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut errors = Vec::new();
+        let (profile, mut value) = loader::load_layers(&mut errors);
+        loader::resolve_secrets(&mut value, &mut errors);
+
+        // Sections are deserialized separately so one bad section doesn't
+        // hide errors in the others
+        fn section<T: serde::de::DeserializeOwned + Default>(
+            value: &serde_json::Value,
+            name: &str,
+            errors: &mut Vec<String>,
+        ) -> T {
+            match value.get(name) {
+                Some(section) => serde_json::from_value(section.clone()).unwrap_or_else(|e| {
+                    errors.push(format!("{}: {}", name, e));
+                    T::default()
+                }),
+                None => T::default(),
+            }
+        }
+
+        if let Some(map) = value.as_object() {
+            for key in map.keys() {
+                if !SECTIONS.contains(&key.as_str()) {
+                    errors.push(format!("{}: unknown section", key));
+                }
+            }
+        }
+
+        let config = AppConfig {
+            profile,
+            server: section(&value, "server", &mut errors),
+            database: section(&value, "database", &mut errors),
+            auth: section(&value, "auth", &mut errors),
+            rate_limit: section(&value, "rate_limit", &mut errors),
+            cors: section(&value, "cors", &mut errors),
+            logging: section(&value, "logging", &mut errors),
+            mail: section(&value, "mail", &mut errors),
+            metrics: section(&value, "metrics", &mut errors),
+            telemetry: section(&value, "telemetry", &mut errors),
+            security: section(&value, "security", &mut errors),
+            api: section(&value, "api", &mut errors),
+            idempotency: section(&value, "idempotency", &mut errors),
+        };
+        loader::scrub_secrets(&mut value);
+
+        config.validate(&mut errors);
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError { errors })
+        }
+    }
+
+    fn validate(&self, errors: &mut Vec<String>) {
+        if self.server.port == 0 {
+            errors.push("server.port: must be between 1 and 65535".to_string());
+        }
+        if let Some(tls) = &self.server.tls {
+            let files = [
+                ("cert_file", Some(&tls.cert_file)),
+                ("key_file", Some(&tls.key_file)),
+                ("client_ca_file", tls.client_ca_file.as_ref()),
+            ];
+            for (field, path) in files {
+                if let Some(path) = path.filter(|path| !Path::new(path).is_file()) {
+                    errors.push(format!("server.tls.{}: {} is not a readable file", field, path));
+                }
+            }
+            if tls.client_auth != ClientAuth::None && tls.client_ca_file.is_none() {
+                errors.push("server.tls.client_ca_file: must be set when client_auth is enabled".to_string());
+            }
+        }
+        if self.server.shutdown_timeout_secs == 0 {
+            errors.push("server.shutdown_timeout_secs: must be greater than 0".to_string());
+        }
+
+        let url = self.database.url.expose();
+        if url.is_empty() {
+            errors.push("database.url: must be set (or DATABASE_URL)".to_string());
+        } else if !url.starts_with("postgres://") && !url.starts_with("postgresql://") {
+            errors.push("database.url: must be a postgres:// URL".to_string());
+        }
+        for (idx, url) in self.database.replica_urls.iter().map(Secret::expose).enumerate() {
+            if !url.starts_with("postgres://") && !url.starts_with("postgresql://") {
+                errors.push(format!("database.replica_urls[{}]: must be a postgres:// URL", idx));
+            }
+        }
+        if self.database.replica_health_check_interval == 0 {
+            errors.push("database.replica_health_check_interval: must be greater than 0".to_string());
+        }
+        if self.database.replica_connection_timeout_ms == 0 {
+            errors.push("database.replica_connection_timeout_ms: must be greater than 0".to_string());
+        }
+
+        if self.auth.jwt_secret.is_empty() {
+            errors.push("auth.jwt_secret: must be set (or JWT_SECRET)".to_string());
+        } else if self.profile == Profile::Prod && self.auth.jwt_secret.expose().len() < 32 {
+            errors.push("auth.jwt_secret: must be at least 32 bytes in prod".to_string());
+        }
+        if self.auth.jwt_verification_secrets.iter().any(Secret::is_empty) {
+            errors.push("auth.jwt_verification_secrets: must not contain empty secrets".to_string());
+        }
+        if self.auth.token_ttl_secs == 0 {
+            errors.push("auth.token_ttl_secs: must be greater than 0".to_string());
+        }
+
+        let password = &self.auth.password;
+        if password.min_length == 0 {
+            errors.push("auth.password.min_length: must be greater than 0".to_string());
+        }
+        if password.max_length < password.min_length {
+            errors.push("auth.password.max_length: must not be less than min_length".to_string());
+        }
+        if password.max_length > 72 {
+            errors.push("auth.password.max_length: bcrypt only uses the first 72 bytes".to_string());
+        }
+        if password.history_size < 0 {
+            errors.push("auth.password.history_size: must not be negative".to_string());
+        }
+        if let Some(path) = &password.breached_passwords_file {
+            if !Path::new(path).is_file() {
+                errors.push(format!("auth.password.breached_passwords_file: {} is not a readable file", path));
+            }
+        }
+
+        if self.rate_limit.requests == 0 {
+            errors.push("rate_limit.requests: must be greater than 0".to_string());
+        }
+        if self.rate_limit.duration_secs == 0 || self.rate_limit.duration_secs > 3600 {
+            errors.push("rate_limit.duration_secs: must be between 1 and 3600".to_string());
+        }
+        if self.rate_limit.api_key_header.parse::<axum::http::HeaderName>().is_err() {
+            errors.push("rate_limit.api_key_header: must be a valid header name".to_string());
+        }
+        for (idx, policy) in self.rate_limit.policies.iter().enumerate() {
+            if !policy.path_prefix.starts_with('/') {
+                errors.push(format!("rate_limit.policies[{}].path_prefix: must start with /", idx));
+            }
+            if policy.requests == 0 || policy.duration_secs == 0 {
+                errors.push(format!("rate_limit.policies[{}]: requests and duration_secs must be greater than 0", idx));
+            }
+            if policy.duration_secs > 3600 {
+                errors.push(format!("rate_limit.policies[{}].duration_secs: must be at most 3600", idx));
+            }
+        }
+
+        // Any-origin access is never implied; every frontend is listed
+        if self.cors.allowed_origins.iter().any(|o| o == "*") {
+            errors.push("cors.allowed_origins: `*` is not allowed; list each origin explicitly".to_string());
+        }
+        for origin in self.cors.allowed_origins.iter().filter(|o| *o != "*" && !valid_origin_pattern(o)) {
+            errors.push(format!(
+                "cors.allowed_origins: `{}` must be scheme://host[:port], optionally with a `*.` subdomain wildcard",
+                origin,
+            ));
+        }
+        for method in &self.cors.allowed_methods {
+            if method.parse::<axum::http::Method>().is_err() {
+                errors.push(format!("cors.allowed_methods: `{}` is not a valid method", method));
+            }
+        }
+        for header in self.cors.allowed_headers.iter().chain(&self.cors.exposed_headers) {
+            if header != "*" && header.parse::<axum::http::HeaderName>().is_err() {
+                errors.push(format!("cors: `{}` is not a valid header name", header));
+            }
+        }
+        if self.cors.exposed_headers.iter().any(|h| h == "*") {
+            errors.push("cors.exposed_headers: list headers explicitly".to_string());
+        }
+        // Browsers reject wildcards on credentialed requests
+        if self.cors.allow_credentials && self.cors.allowed_headers.iter().any(|h| h == "*") {
+            errors.push("cors.allowed_headers: `*` cannot be combined with allow_credentials".to_string());
+        }
+
+        if tracing_subscriber::EnvFilter::try_new(&self.logging.level).is_err() {
+            errors.push(format!("logging.level: `{}` is not a valid filter", self.logging.level));
+        }
+
+        if self.mail.smtp_url.is_some() && self.mail.from_address.is_none() {
+            errors.push("mail.from_address: must be set when mail.smtp_url is set".to_string());
+        }
+        if let Some(from) = &self.mail.from_address {
+            if !validator::validate_email(from) {
+                errors.push("mail.from_address: must be an email address".to_string());
+            }
+        }
+        if self.mail.smtp_url.is_some() && self.mail.verification_url.is_none() {
+            errors.push("mail.verification_url: must be set when mail.smtp_url is set".to_string());
+        }
+        if let Some(url) = &self.mail.verification_url {
+            if !(url.starts_with("https://") || url.starts_with("http://")) || url.contains('?') {
+                errors.push("mail.verification_url: must be an http(s) URL without a query string".to_string());
+            }
+        }
+        if self.auth.require_verified_email && self.mail.smtp_url.is_none() {
+            errors.push("auth.require_verified_email: needs mail.smtp_url to send verification emails".to_string());
+        }
+
+        if self.metrics.enabled {
+            if self.metrics.port == 0 {
+                errors.push("metrics.port: must be between 1 and 65535".to_string());
+            } else if self.metrics.port == self.server.port {
+                errors.push("metrics.port: must differ from server.port".to_string());
+            }
+        }
+
+        let security = &self.security;
+        for (field, value) in [
+            ("content_security_policy", &security.content_security_policy),
+            ("referrer_policy", &security.referrer_policy),
+            ("frame_options", &security.frame_options),
+        ] {
+            if axum::http::HeaderValue::from_str(value).is_err() {
+                errors.push(format!("security.{}: not a valid header value", field));
+            }
+        }
+        if security.max_body_bytes == 0 || security.request_timeout_secs == 0 {
+            errors.push("security: max_body_bytes and request_timeout_secs must be greater than 0".to_string());
+        }
+        for (idx, route) in security.routes.iter().enumerate() {
+            if !route.path_prefix.starts_with('/') {
+                errors.push(format!("security.routes[{}].path_prefix: must start with /", idx));
+            }
+            if route.max_body_bytes == Some(0) || route.request_timeout_secs == Some(0) {
+                errors.push(format!("security.routes[{}]: limits must be greater than 0", idx));
+            }
+            if let Some(csp) = &route.content_security_policy {
+                if axum::http::HeaderValue::from_str(csp).is_err() {
+                    errors.push(format!("security.routes[{}].content_security_policy: not a valid header value", idx));
+                }
+            }
+        }
+        for proxy in &security.trusted_proxies {
+            if proxy.parse::<ipnet::IpNet>().is_err() && proxy.parse::<IpAddr>().is_err() {
+                errors.push(format!("security.trusted_proxies: `{}` is not an IP address or CIDR", proxy));
+            }
+        }
+
+        for (idx, version) in self.api.versions.iter().enumerate() {
+            if !API_VERSIONS.contains(&version.version.as_str()) && version.version != UNVERSIONED {
+                errors.push(format!(
+                    "api.versions[{}].version: unknown version `{}` (expected one of {}, {})",
+                    idx, version.version, API_VERSIONS.join(", "), UNVERSIONED
+                ));
+            }
+            let parse = |field: &str, value: &Option<String>, errors: &mut Vec<String>| {
+                value.as_deref().and_then(|value| {
+                    chrono::DateTime::parse_from_rfc3339(value)
+                        .map_err(|_| errors.push(format!("api.versions[{}].{}: must be an RFC 3339 timestamp", idx, field)))
+                        .ok()
+                })
+            };
+            let deprecated_at = parse("deprecated_at", &version.deprecated_at, errors);
+            let sunset_at = parse("sunset_at", &version.sunset_at, errors);
+            if let (Some(deprecated_at), Some(sunset_at)) = (deprecated_at, sunset_at) {
+                if sunset_at < deprecated_at {
+                    errors.push(format!("api.versions[{}].sunset_at: must not be before deprecated_at", idx));
+                }
+            }
+            if version.version == UNVERSIONED && version.deprecated_at.is_none() {
+                errors.push(format!("api.versions[{}].deprecated_at: is required for `{}`", idx, UNVERSIONED));
+            }
+            if let Some(link) = &version.deprecation_link {
+                if (!link.starts_with("http://") && !link.starts_with("https://") && !link.starts_with('/'))
+                    || axum::http::HeaderValue::from_str(link).is_err()
+                {
+                    errors.push(format!("api.versions[{}].deprecation_link: must be a URL or absolute path", idx));
+                }
+            }
+        }
+
+        if !(1..=7 * 24 * 3600).contains(&self.idempotency.ttl_secs) {
+            errors.push("idempotency.ttl_secs: must be between 1 and 604800 (7 days)".to_string());
+        }
+
+        if !(0.0..=1.0).contains(&self.telemetry.sample_ratio) {
+            errors.push("telemetry.sample_ratio: must be between 0 and 1".to_string());
+        }
+        if let Some(endpoint) = &self.telemetry.otlp_endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                errors.push("telemetry.otlp_endpoint: must be an http:// or https:// URL".to_string());
+            }
+        }
+    }
+}
+
+impl AuthConfig {
+    pub fn password_policy(&self) -> Result<PasswordPolicy, ConfigError> {
+        let password = &self.password;
+        let breached = match &password.breached_passwords_file {
+            Some(path) => {
+                let dataset = BreachedPasswordDataset::load(Path::new(path)).map_err(|e| ConfigError {
+                    errors: vec![format!("auth.password.breached_passwords_file: failed to load {}: {}", path, e)],
+                })?;
+                Some(Arc::new(dataset) as _)
+            }
+            None => None,
+        };
+
+        Ok(PasswordPolicy {
+            min_length: password.min_length,
+            max_length: password.max_length,
+            require_uppercase: password.require_uppercase,
+            require_lowercase: password.require_lowercase,
+            require_digit: password.require_digit,
+            require_special: password.require_special,
+            disallow_email: password.disallow_email,
+            history_size: password.history_size,
+            breached,
+        })
+    }
+}
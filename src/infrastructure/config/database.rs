@@ -1,18 +1,30 @@
+use std::time::Duration;
 use diesel::pg::PgConnection;
 use diesel::r2d2::{self, ConnectionManager};
-use dotenv::dotenv;
-use std::env;
+use crate::infrastructure::config::app::DatabaseConfig;
 
 pub type DbPool = r2d2::Pool<ConnectionManager<PgConnection>>;
 
-pub fn establish_connection_pool() -> DbPool {
-    dotenv().ok();
-    
-    let database_url = env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set");
-        
-    let manager = ConnectionManager::<PgConnection>::new(database_url);
+pub fn establish_connection_pool(config: &DatabaseConfig) -> DbPool {
+    let manager = ConnectionManager::<PgConnection>::new(config.primary_url().expose());
     r2d2::Pool::builder()
         .build(manager)
         .expect("Failed to create pool")
+}
+
+// Replica pools are built unchecked so an unreachable replica doesn't stop
+// startup; the router's health checks take it out of rotation instead. A
+// failed checkout also marks the replica unhealthy, so the short timeout is
+// paid once rather than on every read.
+pub fn establish_replica_pools(config: &DatabaseConfig) -> Vec<DbPool> {
+    let timeout = Duration::from_millis(config.replica_connection_timeout_ms);
+    config.replica_urls()
+        .iter()
+        .map(|url| {
+            let manager = ConnectionManager::<PgConnection>::new(url.expose());
+            r2d2::Pool::builder()
+                .connection_timeout(timeout)
+                .build_unchecked(manager)
+        })
+        .collect()
 } 
\ No newline at end of file
@@ -0,0 +1,338 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde_json::{Map, Value};
+use zeroize::Zeroize;
+use crate::infrastructure::{
+    config::app::Profile,
+    secrets::{file::read_secret_file, SecretResolver},
+};
+
+// Prefix for structured overrides, e.g. `APP__SERVER__PORT=8080`
+const ENV_PREFIX: &str = "APP__";
+
+enum EnvKind {
+    Str,
+    List,
+    Scalar,
+}
+
+// Flat variables from before the config file existed, kept as overrides
+const LEGACY_ENV: &[(&str, &[&str], EnvKind)] = &[
+    ("PORT", &["server", "port"], EnvKind::Scalar),
+    ("DATABASE_URL", &["database", "url"], EnvKind::Str),
+    ("DATABASE_REPLICA_URLS", &["database", "replica_urls"], EnvKind::List),
+    ("DATABASE_REPLICA_HEALTH_INTERVAL", &["database", "replica_health_check_interval"], EnvKind::Scalar),
+    ("DATABASE_PASSWORD", &["database", "password"], EnvKind::Str),
+    ("JWT_SECRET", &["auth", "jwt_secret"], EnvKind::Str),
+    ("RATE_LIMIT_REQUEST", &["rate_limit", "requests"], EnvKind::Scalar),
+    ("RATE_LIMIT_DURATION", &["rate_limit", "duration_secs"], EnvKind::Scalar),
+    ("PASSWORD_MIN_LENGTH", &["auth", "password", "min_length"], EnvKind::Scalar),
+    ("PASSWORD_MAX_LENGTH", &["auth", "password", "max_length"], EnvKind::Scalar),
+    ("PASSWORD_REQUIRE_UPPERCASE", &["auth", "password", "require_uppercase"], EnvKind::Scalar),
+    ("PASSWORD_REQUIRE_LOWERCASE", &["auth", "password", "require_lowercase"], EnvKind::Scalar),
+    ("PASSWORD_REQUIRE_DIGIT", &["auth", "password", "require_digit"], EnvKind::Scalar),
+    ("PASSWORD_REQUIRE_SPECIAL", &["auth", "password", "require_special"], EnvKind::Scalar),
+    ("PASSWORD_DISALLOW_EMAIL", &["auth", "password", "disallow_email"], EnvKind::Scalar),
+    ("PASSWORD_HISTORY_SIZE", &["auth", "password", "history_size"], EnvKind::Scalar),
+    ("BREACHED_PASSWORDS_FILE", &["auth", "password", "breached_passwords_file"], EnvKind::Str),
+    ("CORS_ALLOWED_ORIGINS", &["cors", "allowed_origins"], EnvKind::List),
+    ("LOG_LEVEL", &["logging", "level"], EnvKind::Str),
+    ("LOG_FORMAT", &["logging", "format"], EnvKind::Str),
+];
+
+// Settings that may hold a `file:`, `enc:` or `vault:` reference and may be
+// given through a `*_FILE` variable
+const SECRET_PATHS: &[&[&str]] = &[
+    &["database", "url"],
+    &["database", "replica_urls"],
+    &["database", "password"],
+    &["auth", "jwt_secret"],
+    &["auth", "jwt_verification_secrets"],
+    &["rate_limit", "api_keys"],
+    &["mail", "smtp_url"],
+];
+
+// Reads `NAME`, or the file named by `NAME_FILE` as mounted by Docker or
+// Kubernetes secrets
+fn env_or_file(name: &str, errors: &mut Vec<String>) -> Option<String> {
+    if let Ok(raw) = env::var(name) {
+        return Some(raw);
+    }
+    let path = env::var(format!("{}_FILE", name)).ok()?;
+    read_secret_file(&path)
+        .map_err(|e| errors.push(format!("{}_FILE: {}", name, e)))
+        .ok()
+}
+
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn set_path(root: &mut Value, path: &[&str], value: Value) {
+    let mut overlay = value;
+    for key in path.iter().rev() {
+        let mut map = Map::new();
+        map.insert(key.to_string(), overlay);
+        overlay = Value::Object(map);
+    }
+    merge(root, overlay);
+}
+
+fn parse_file(path: &Path) -> Result<Value, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str::<toml::Value>(&contents)
+            .map_err(|e| e.to_string())
+            .and_then(|value| serde_json::to_value(value).map_err(|e| e.to_string())),
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&contents).map_err(|e| e.to_string()),
+        _ => Err("unsupported extension (expected .toml, .yaml or .yml)".to_string()),
+    }
+}
+
+fn find_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    ["toml", "yaml", "yml"]
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", name, ext)))
+        .find(|path| path.is_file())
+}
+
+// Env values are untyped; numbers, booleans and JSON arrays are recognised,
+// anything else is kept as a string.
+fn coerce(raw: &str) -> Value {
+    match serde_json::from_str::<Value>(raw) {
+        Ok(value @ (Value::Number(_) | Value::Bool(_) | Value::Array(_) | Value::String(_))) => value,
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+// Secrets are never coerced: a numeric password or one wrapped in quotes
+// must reach `Secret` verbatim. List-valued secrets still accept a JSON
+// array of strings.
+fn raw_secret(raw: &str) -> Value {
+    match serde_json::from_str::<Value>(raw) {
+        Ok(Value::Array(items)) if items.iter().all(Value::is_string) => Value::Array(items),
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+fn is_secret_path(path: &[String]) -> bool {
+    SECRET_PATHS.iter().any(|secret| secret.iter().eq(path.iter()))
+}
+
+// Required: silently falling back to dev would ship relaxed settings (no
+// HSTS, credentialed localhost CORS) to a deployment that forgot to set it.
+// Validation continues as prod so every other problem is still reported.
+pub fn profile(errors: &mut Vec<String>) -> Profile {
+    match env::var("APP_PROFILE").as_deref() {
+        Ok("dev") => Profile::Dev,
+        Ok("test") => Profile::Test,
+        Ok("prod") => Profile::Prod,
+        Ok(other) => {
+            errors.push(format!("APP_PROFILE: unknown profile `{}` (expected dev, test or prod)", other));
+            Profile::Prod
+        }
+        Err(_) => {
+            errors.push("APP_PROFILE: must be set to dev, test or prod".to_string());
+            Profile::Prod
+        }
+    }
+}
+
+// Config files that exist for `profile`, lowest precedence first
+pub fn config_files(profile: Profile, errors: &mut Vec<String>) -> Vec<PathBuf> {
+    let dir = PathBuf::from(env::var("CONFIG_DIR").unwrap_or_else(|_| "config".to_string()));
+
+    let mut files: Vec<PathBuf> = ["default", profile.as_str()]
+        .iter()
+        .filter_map(|name| find_file(&dir, name))
+        .collect();
+
+    if let Ok(path) = env::var("APP_CONFIG_FILE") {
+        let path = PathBuf::from(path);
+        if path.is_file() {
+            files.push(path);
+        } else {
+            errors.push(format!("APP_CONFIG_FILE: {} does not exist", path.display()));
+        }
+    }
+
+    files
+}
+
+pub fn load_layers(errors: &mut Vec<String>) -> (Profile, Value) {
+    let profile = profile(errors);
+    let mut value = Value::Object(Map::new());
+
+    for path in config_files(profile, errors) {
+        match parse_file(&path) {
+            Ok(layer) => merge(&mut value, layer),
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    for (name, path, kind) in LEGACY_ENV {
+        if let Some(raw) = env_or_file(name, errors) {
+            let layer = match kind {
+                EnvKind::Str => Value::String(raw),
+                EnvKind::Scalar => coerce(&raw),
+                EnvKind::List => Value::Array(
+                    raw.split(',')
+                        .map(str::trim)
+                        .filter(|item| !item.is_empty())
+                        .map(|item| Value::String(item.to_string()))
+                        .collect(),
+                ),
+            };
+            set_path(&mut value, path, layer);
+        }
+    }
+
+    // Structured overrides win over everything else
+    let mut overrides: Vec<(String, String)> = env::vars()
+        .filter(|(key, _)| key.starts_with(ENV_PREFIX))
+        .collect();
+    overrides.sort();
+
+    for (key, raw) in overrides {
+        let mut path: Vec<String> = key[ENV_PREFIX.len()..]
+            .split("__")
+            .map(str::to_lowercase)
+            .collect();
+
+        // `APP__AUTH__JWT_SECRET_FILE` reads the secret from a file. Only
+        // secret settings qualify; `breached_passwords_file` is a real key.
+        let secret_file = path
+            .last()
+            .and_then(|last| last.strip_suffix("_file"))
+            .map(str::to_string)
+            .filter(|stripped| {
+                let mut candidate = path[..path.len() - 1].to_vec();
+                candidate.push(stripped.clone());
+                is_secret_path(&candidate)
+            });
+
+        let layer = match secret_file {
+            Some(stripped) => {
+                *path.last_mut().unwrap() = stripped;
+                match read_secret_file(&raw) {
+                    Ok(contents) => raw_secret(&contents),
+                    Err(e) => {
+                        errors.push(format!("{}: {}", key, e));
+                        continue;
+                    }
+                }
+            }
+            None if is_secret_path(&path) => raw_secret(&raw),
+            None => coerce(&raw),
+        };
+
+        let path: Vec<&str> = path.iter().map(String::as_str).collect();
+        set_path(&mut value, &path, layer);
+    }
+
+    (profile, value)
+}
+
+fn secret_values<'a>(root: &'a mut Value, path: &[&str]) -> Vec<&'a mut String> {
+    let mut node = root;
+    for key in path {
+        match node.get_mut(*key) {
+            Some(next) => node = next,
+            None => return Vec::new(),
+        }
+    }
+    match node {
+        Value::String(s) => vec![s],
+        Value::Array(items) => items
+            .iter_mut()
+            .filter_map(|item| match item {
+                Value::String(s) => Some(s),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Replaces secret references with their values. Providers are only set up
+// when a reference is actually present.
+pub fn resolve_secrets(value: &mut Value, errors: &mut Vec<String>) {
+    let mut resolver: Option<SecretResolver> = None;
+
+    for path in SECRET_PATHS {
+        for secret in secret_values(value, path) {
+            if !SecretResolver::is_reference(secret) {
+                continue;
+            }
+
+            if resolver.is_none() {
+                match SecretResolver::from_env() {
+                    Ok(r) => resolver = Some(r),
+                    Err(e) => {
+                        errors.push(format!("secrets: {}", e));
+                        return;
+                    }
+                }
+            }
+
+            match resolver.as_ref().unwrap().resolve(secret) {
+                Ok(mut resolved) => {
+                    std::mem::swap(secret, &mut resolved);
+                    resolved.zeroize();
+                }
+                Err(e) => errors.push(format!("{}: {}", path.join("."), e)),
+            }
+        }
+    }
+}
+
+// Wipes secret values from the merged document once they have been copied
+// into the typed config
+pub fn scrub_secrets(value: &mut Value) {
+    for path in SECRET_PATHS {
+        for secret in secret_values(value, path) {
+            secret.zeroize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    #[test]
+    fn coerce_recognises_scalars_and_arrays() {
+        assert_eq!(coerce("8080"), json!(8080));
+        assert_eq!(coerce("true"), json!(true));
+        assert_eq!(coerce(r#"["a","b"]"#), json!(["a", "b"]));
+        assert_eq!(coerce("info"), json!("info"));
+        assert_eq!(coerce(r#""quoted""#), json!("quoted"));
+    }
+
+    #[test]
+    fn secrets_keep_their_raw_value() {
+        assert_eq!(raw_secret("123456"), json!("123456"));
+        assert_eq!(raw_secret("false"), json!("false"));
+        assert_eq!(raw_secret(r#""pa55""#), json!(r#""pa55""#));
+        assert_eq!(raw_secret(r#"["k1","k2"]"#), json!(["k1", "k2"]));
+        assert_eq!(raw_secret("[1,2]"), json!("[1,2]"));
+    }
+
+    #[test]
+    fn secret_paths_match_whole_keys() {
+        let path = |keys: &[&str]| keys.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+        assert!(is_secret_path(&path(&["auth", "jwt_secret"])));
+        assert!(!is_secret_path(&path(&["auth", "password", "breached_passwords_file"])));
+        assert!(!is_secret_path(&path(&["server", "port"])));
+    }
+} 
\ No newline at end of file
@@ -1,2 +1,4 @@
 pub mod database;
-pub mod app; 
\ No newline at end of file
+pub mod app;
+pub mod loader;
+pub mod reload; 
\ No newline at end of file
@@ -0,0 +1,276 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use arc_swap::ArcSwap;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+use crate::infrastructure::{
+    auth::jwt::JwtService,
+    config::{
+        app::{AppConfig, AuthConfig, CorsConfig, LoggingConfig, RateLimitConfig},
+        loader,
+    },
+    shutdown::ShutdownHandle,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub type LogHandle = reload::Handle<EnvFilter, Registry>;
+
+// Settings that can change without a restart. Everything else in
+// `AppConfig` is read once at running.
+#[derive(Debug, Clone)]
+pub struct RuntimeSettings {
+    pub rate_limit: RateLimitConfig,
+    pub cors: CorsConfig,
+    pub logging: LoggingConfig,
+    pub auth: AuthConfig,
+}
+
+impl From<&AppConfig> for RuntimeSettings {
+    fn from(config: &AppConfig) -> Self {
+        Self {
+            rate_limit: config.rate_limit.clone(),
+            cors: config.cors.clone(),
+            logging: config.logging.clone(),
+            auth: config.auth.clone(),
+        }
+    }
+}
+
+pub type SharedSettings = Arc<ArcSwap<RuntimeSettings>>;
+
+pub struct Reloader {
+    // Configuration currently in effect: the startup config plus every
+    // change applied by a reload since
+    running: AppConfig,
+    settings: SharedSettings,
+    jwt_service: JwtService,
+    log_handle: LogHandle,
+}
+
+impl Reloader {
+    pub fn new(running: AppConfig, settings: SharedSettings, jwt_service: JwtService, log_handle: LogHandle) -> Self {
+        Self {
+            running,
+            settings,
+            jwt_service,
+            log_handle,
+        }
+    }
+
+    // Reloads on SIGHUP and whenever a config file's mtime changes, until
+    // shutdown
+    pub fn spawn(mut self, shutdown: ShutdownHandle) {
+        let signal = shutdown.clone();
+        shutdown.spawn(async move {
+            #[cfg(unix)]
+            let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+                .expect("Failed to install SIGHUP handler");
+
+            let mut ticker = tokio::time::interval(POLL_INTERVAL);
+            let mut last_seen = Self::modified_times(&self.running);
+
+            loop {
+                #[cfg(unix)]
+                let hangup_received = tokio::select! {
+                    _ = hangup.recv() => true,
+                    _ = ticker.tick() => false,
+                    _ = signal.signalled() => return,
+                };
+                #[cfg(not(unix))]
+                let hangup_received = tokio::select! {
+                    _ = ticker.tick() => false,
+                    _ = signal.signalled() => return,
+                };
+
+                let modified = Self::modified_times(&self.running);
+                if hangup_received {
+                    tracing::info!("SIGHUP received, reloading configuration");
+                } else if modified == last_seen {
+                    continue;
+                } else {
+                    tracing::info!("Configuration file changed, reloading");
+                }
+                last_seen = modified;
+
+                self.reload().await;
+            }
+        });
+    }
+
+    fn modified_times(config: &AppConfig) -> Vec<(PathBuf, Option<SystemTime>)> {
+        let mut errors = Vec::new();
+        loader::config_files(config.profile, &mut errors)
+            .into_iter()
+            .map(|path| {
+                let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+                (path, modified)
+            })
+            .collect()
+    }
+
+    async fn reload(&mut self) {
+        // Reads files and may fetch secrets from Vault over blocking HTTP
+        let config = match tokio::task::spawn_blocking(AppConfig::load).await {
+            Ok(Ok(config)) => config,
+            Ok(Err(e)) => {
+                tracing::error!("Rejected new configuration, keeping the current one: {}", e);
+                return;
+            }
+            Err(e) => {
+                tracing::error!("Configuration reload failed, keeping the current one: {}", e);
+                return;
+            }
+        };
+
+        let old = self.settings.load_full();
+        let new = RuntimeSettings::from(&config);
+
+        let changes = describe_changes(&old, &new);
+        if changes.is_empty() {
+            tracing::info!("Configuration reloaded, no runtime settings changed");
+        }
+        for change in &changes {
+            tracing::info!("Configuration changed: {}", change);
+        }
+
+        for section in restart_required(&self.running, &config) {
+            tracing::warn!("Configuration change to `{}` requires a restart and was not applied", section);
+        }
+
+        if old.logging.level != new.logging.level {
+            if let Err(e) = self.log_handle.reload(EnvFilter::new(&new.logging.level)) {
+                tracing::error!("Failed to apply log level: {}", e);
+            }
+        }
+        self.jwt_service.reload_keys(&new.auth);
+        self.settings.store(Arc::new(new));
+        self.running = applied(&self.running, &config);
+    }
+}
+
+// `running` with the settings a reload applies taken from `loaded`; sections
+// that need a restart keep their running values, so they are reported again
+// until the process restarts, and not at all once reverted
+fn applied(running: &AppConfig, loaded: &AppConfig) -> AppConfig {
+    let mut config = running.clone();
+    config.rate_limit = RateLimitConfig {
+        backend: running.rate_limit.backend,
+        ..loaded.rate_limit.clone()
+    };
+    config.cors.allowed_origins = loaded.cors.allowed_origins.clone();
+    config.logging.level = loaded.logging.level.clone();
+    config.auth.jwt_secret = loaded.auth.jwt_secret.clone();
+    config.auth.jwt_verification_secrets = loaded.auth.jwt_verification_secrets.clone();
+    config
+}
+
+fn describe_changes(old: &RuntimeSettings, new: &RuntimeSettings) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    if old.rate_limit.requests != new.rate_limit.requests
+        || old.rate_limit.duration_secs != new.rate_limit.duration_secs
+    {
+        changes.push(format!(
+            "rate_limit {}/{}s -> {}/{}s",
+            old.rate_limit.requests, old.rate_limit.duration_secs,
+            new.rate_limit.requests, new.rate_limit.duration_secs,
+        ));
+    }
+    if old.rate_limit.api_keys != new.rate_limit.api_keys {
+        changes.push(format!(
+            "rate_limit.api_keys ({} -> {} keys)",
+            old.rate_limit.api_keys.len(),
+            new.rate_limit.api_keys.len(),
+        ));
+    }
+    if old.rate_limit.fail_open != new.rate_limit.fail_open {
+        changes.push(format!("rate_limit.fail_open {} -> {}", old.rate_limit.fail_open, new.rate_limit.fail_open));
+    }
+    if old.rate_limit.policies != new.rate_limit.policies
+        || old.rate_limit.api_key_header != new.rate_limit.api_key_header
+    {
+        changes.push(format!(
+            "rate_limit.policies {:?} -> {:?}",
+            old.rate_limit.policies, new.rate_limit.policies,
+        ));
+    }
+    if old.cors.allowed_origins != new.cors.allowed_origins {
+        changes.push(format!(
+            "cors.allowed_origins {:?} -> {:?}",
+            old.cors.allowed_origins, new.cors.allowed_origins,
+        ));
+    }
+    if old.logging.level != new.logging.level {
+        changes.push(format!("logging.level {} -> {}", old.logging.level, new.logging.level));
+    }
+    // Never log the secrets themselves
+    if old.auth.jwt_secret != new.auth.jwt_secret
+        || old.auth.jwt_verification_secrets != new.auth.jwt_verification_secrets
+    {
+        changes.push(format!(
+            "JWT keys rotated ({} -> {} verification keys)",
+            old.auth.jwt_verification_secrets.len() + 1,
+            new.auth.jwt_verification_secrets.len() + 1,
+        ));
+    }
+
+    changes
+}
+
+fn differs<T: serde::Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() != serde_json::to_value(b).ok()
+}
+
+fn restart_required(running: &AppConfig, new: &AppConfig) -> Vec<&'static str> {
+    let mut sections = Vec::new();
+
+    if differs(&running.server, &new.server) {
+        sections.push("server");
+    }
+    // Secrets serialize redacted, so these sections are compared directly
+    if running.database != new.database {
+        sections.push("database");
+    }
+    if differs(&running.auth.password, &new.auth.password)
+        || running.auth.token_ttl_secs != new.auth.token_ttl_secs
+        || running.auth.require_verified_email != new.auth.require_verified_email
+    {
+        sections.push("auth.password / auth.token_ttl_secs / auth.require_verified_email");
+    }
+    if running.mail != new.mail {
+        sections.push("mail");
+    }
+    // Only the origin list is checked per request
+    if running.cors.allow_credentials != new.cors.allow_credentials
+        || running.cors.allowed_methods != new.cors.allowed_methods
+        || running.cors.allowed_headers != new.cors.allowed_headers
+        || running.cors.exposed_headers != new.cors.exposed_headers
+        || running.cors.max_age_secs != new.cors.max_age_secs
+    {
+        sections.push("cors (other than allowed_origins)");
+    }
+    if running.rate_limit.backend != new.rate_limit.backend {
+        sections.push("rate_limit.backend");
+    }
+    if running.logging.format != new.logging.format {
+        sections.push("logging.format");
+    }
+    if differs(&running.metrics, &new.metrics) {
+        sections.push("metrics");
+    }
+    if differs(&running.telemetry, &new.telemetry) {
+        sections.push("telemetry");
+    }
+    if differs(&running.security, &new.security) {
+        sections.push("security");
+    }
+    if differs(&running.api, &new.api) {
+        sections.push("api");
+    }
+    if differs(&running.idempotency, &new.idempotency) {
+        sections.push("idempotency");
+    }
+
+    sections
+} 
\ No newline at end of file
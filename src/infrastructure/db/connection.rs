@@ -0,0 +1,73 @@
+use std::sync::{Arc, Mutex};
+use diesel::pg::PgConnection;
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use crate::infrastructure::{
+    config::database::DbPool,
+    db::replica::{self, DbRouter},
+    error::AppError,
+    metrics,
+};
+
+pub type PgPooledConnection = PooledConnection<ConnectionManager<PgConnection>>;
+
+// Where a repository gets its connection from: a fresh pooled connection per
+// call, a primary/replica router, or the connection owned by an open transaction.
+#[derive(Clone)]
+pub enum ConnectionSource {
+    Pool(DbPool),
+    Routed(Arc<DbRouter>),
+    Transaction(Arc<Mutex<PgPooledConnection>>),
+}
+
+impl ConnectionSource {
+    pub fn run<R>(
+        &self,
+        f: impl FnOnce(&mut PgConnection) -> Result<R, AppError>,
+    ) -> Result<R, AppError> {
+        match self {
+            ConnectionSource::Pool(pool) => {
+                let conn = &mut metrics::timed_get(pool, "primary")
+                    .map_err(AppError::from)?;
+                f(conn)
+            }
+            ConnectionSource::Routed(router) => {
+                let conn = &mut metrics::timed_get(router.primary(), "primary")
+                    .map_err(AppError::from)?;
+                replica::mark_wrote_primary();
+                f(conn)
+            }
+            ConnectionSource::Transaction(conn) => {
+                let mut conn = conn.lock()
+                    .map_err(|_| AppError::InternalServerError)?;
+                f(&mut conn)
+            }
+        }
+    }
+
+    // Read-only queries may be served by a replica when one is configured
+    pub fn run_read<R>(
+        &self,
+        f: impl FnOnce(&mut PgConnection) -> Result<R, AppError>,
+    ) -> Result<R, AppError> {
+        let ConnectionSource::Routed(router) = self else {
+            return self.run(f);
+        };
+
+        match router.read_pool() {
+            (pool, Some(idx)) => match metrics::timed_get(pool, &format!("replica_{}", idx)) {
+                Ok(mut conn) => f(&mut conn),
+                Err(_) => {
+                    router.mark_unhealthy(idx);
+                    let conn = &mut metrics::timed_get(router.primary(), "primary")
+                        .map_err(AppError::from)?;
+                    f(conn)
+                }
+            },
+            (pool, None) => {
+                let conn = &mut metrics::timed_get(pool, "primary")
+                    .map_err(AppError::from)?;
+                f(conn)
+            }
+        }
+    }
+}
\ No newline at end of file
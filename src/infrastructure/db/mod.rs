@@ -0,0 +1,3 @@
+pub mod connection;
+pub mod replica;
+pub mod transaction;
@@ -0,0 +1,124 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use diesel::prelude::*;
+use crate::infrastructure::{config::database::DbPool, shutdown::ShutdownHandle};
+
+tokio::task_local! {
+    // Set once the current request has written to the primary
+    static WROTE_PRIMARY: Cell<bool>;
+}
+
+// Runs `fut` with its own read-your-writes flag. Reads made after a write
+// inside the scope are served by the primary.
+pub async fn sticky_scope<F: Future>(fut: F) -> F::Output {
+    WROTE_PRIMARY.scope(Cell::new(false), fut).await
+}
+
+pub fn mark_wrote_primary() {
+    let _ = WROTE_PRIMARY.try_with(|wrote| wrote.set(true));
+}
+
+fn wrote_primary() -> bool {
+    WROTE_PRIMARY.try_with(Cell::get).unwrap_or(false)
+}
+
+struct Replica {
+    pool: DbPool,
+    healthy: AtomicBool,
+}
+
+pub struct DbRouter {
+    primary: DbPool,
+    replicas: Vec<Replica>,
+    next: AtomicUsize,
+}
+
+impl DbRouter {
+    pub fn new(primary: DbPool, replicas: Vec<DbPool>) -> Self {
+        Self {
+            primary,
+            replicas: replicas
+                .into_iter()
+                .map(|pool| Replica { pool, healthy: AtomicBool::new(true) })
+                .collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn primary(&self) -> &DbPool {
+        &self.primary
+    }
+
+    // Every pool with the name used for it in metrics
+    pub fn pools(&self) -> Vec<(String, &DbPool)> {
+        std::iter::once(("primary".to_string(), &self.primary))
+            .chain(self.replicas.iter().enumerate().map(|(idx, r)| (format!("replica_{}", idx), &r.pool)))
+            .collect()
+    }
+
+    // Round-robin over healthy replicas, falling back to the primary when
+    // none are healthy or the current request has already written.
+    pub fn read_pool(&self) -> (&DbPool, Option<usize>) {
+        if self.replicas.is_empty() || wrote_primary() {
+            return (&self.primary, None);
+        }
+
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        for i in 0..self.replicas.len() {
+            let idx = (start + i) % self.replicas.len();
+            if self.replicas[idx].healthy.load(Ordering::Relaxed) {
+                return (&self.replicas[idx].pool, Some(idx));
+            }
+        }
+
+        (&self.primary, None)
+    }
+
+    pub fn mark_unhealthy(&self, idx: usize) {
+        if let Some(replica) = self.replicas.get(idx) {
+            if replica.healthy.swap(false, Ordering::Relaxed) {
+                tracing::warn!("Database replica {} marked unhealthy", idx);
+            }
+        }
+    }
+
+    fn check_replicas(&self) {
+        for (idx, replica) in self.replicas.iter().enumerate() {
+            let healthy = replica.pool
+                .get()
+                .map(|mut conn| diesel::sql_query("SELECT 1").execute(&mut conn).is_ok())
+                .unwrap_or(false);
+
+            let was_healthy = replica.healthy.swap(healthy, Ordering::Relaxed);
+            if healthy && !was_healthy {
+                tracing::info!("Database replica {} is healthy again", idx);
+            } else if !healthy && was_healthy {
+                tracing::warn!("Database replica {} failed health check", idx);
+            }
+        }
+    }
+
+    // Stops on shutdown, releasing its reference to the pools
+    pub fn spawn_health_checks(self: &Arc<Self>, interval: Duration, shutdown: ShutdownHandle) {
+        if self.replicas.is_empty() {
+            return;
+        }
+
+        let router = self.clone();
+        let signal = shutdown.clone();
+        shutdown.spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = signal.signalled() => return,
+                }
+                let router = router.clone();
+                let _ = tokio::task::spawn_blocking(move || router.check_replicas()).await;
+            }
+        });
+    }
+}
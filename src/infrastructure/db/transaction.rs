@@ -0,0 +1,159 @@
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use diesel::connection::{AnsiTransactionManager, TransactionManager};
+use crate::{
+    domain::{
+        models::password_policy::PasswordPolicy,
+        repositories::unit_of_work::{Transaction, UnitOfWork},
+    },
+    infrastructure::{
+        config::database::DbPool,
+        db::connection::{ConnectionSource, PgPooledConnection},
+        error::AppError,
+        metrics,
+        repositories::{
+            password_history_repository::DieselPasswordHistoryRepository,
+            user_repository::DieselUserRepository,
+        },
+    },
+};
+
+#[derive(Clone)]
+pub struct DieselUnitOfWork {
+    pool: DbPool,
+    policy: Arc<PasswordPolicy>,
+}
+
+impl DieselUnitOfWork {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            pool,
+            policy: Arc::new(PasswordPolicy::default()),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: Arc<PasswordPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+#[async_trait]
+impl UnitOfWork for DieselUnitOfWork {
+    type Tx = DieselTransaction;
+
+    async fn begin(&self) -> Result<DieselTransaction, AppError> {
+        let mut conn = metrics::timed_get(&self.pool, "primary")
+            .map_err(AppError::from)?;
+
+        AnsiTransactionManager::begin_transaction(&mut *conn)
+            .map_err(AppError::from)?;
+
+        let conn = Arc::new(Mutex::new(conn));
+        let users = DieselUserRepository::with_source(ConnectionSource::Transaction(conn.clone()))
+            .with_policy(self.policy.clone());
+        let password_history = DieselPasswordHistoryRepository::with_source(ConnectionSource::Transaction(conn.clone()));
+
+        Ok(DieselTransaction {
+            conn,
+            users,
+            password_history,
+            finished: false,
+        })
+    }
+}
+
+pub struct DieselTransaction {
+    conn: Arc<Mutex<PgPooledConnection>>,
+    users: DieselUserRepository,
+    password_history: DieselPasswordHistoryRepository,
+    finished: bool,
+}
+
+impl DieselTransaction {
+    fn finish(
+        &mut self,
+        f: fn(&mut PgPooledConnection) -> diesel::QueryResult<()>,
+    ) -> Result<(), AppError> {
+        self.finished = true;
+        let mut conn = self.conn.lock()
+            .map_err(|_| AppError::InternalServerError)?;
+        f(&mut conn).map_err(AppError::from)
+    }
+}
+
+#[async_trait]
+impl Transaction for DieselTransaction {
+    type Users = DieselUserRepository;
+    type PasswordHistory = DieselPasswordHistoryRepository;
+
+    fn users(&self) -> &DieselUserRepository {
+        &self.users
+    }
+
+    fn password_history(&self) -> &DieselPasswordHistoryRepository {
+        &self.password_history
+    }
+
+    async fn commit(mut self) -> Result<(), AppError> {
+        self.finish(|conn| AnsiTransactionManager::commit_transaction(&mut **conn))
+    }
+
+    async fn rollback(mut self) -> Result<(), AppError> {
+        self.finish(|conn| AnsiTransactionManager::rollback_transaction(&mut **conn))
+    }
+}
+
+impl Drop for DieselTransaction {
+    fn drop(&mut self) {
+        // Never hand a connection with an open transaction back to the pool
+        if !self.finished {
+            if let Ok(mut conn) = self.conn.lock() {
+                if let Err(e) = AnsiTransactionManager::rollback_transaction(&mut **conn) {
+                    tracing::error!("Failed to roll back abandoned transaction: {}", e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::{r2d2::{self, ConnectionManager}, PgConnection};
+    use super::*;
+    use crate::domain::{
+        models::user::UserRole,
+        repositories::{
+            password_history_repository::PasswordHistoryRepository,
+            user_repository::UserRepository,
+        },
+    };
+
+    fn pool() -> DbPool {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        r2d2::Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<PgConnection>::new(url))
+            .expect("failed to connect to DATABASE_URL")
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn failed_transaction_rolls_back_every_repository() {
+        let pool = pool();
+        let uow = DieselUnitOfWork::new(pool.clone());
+        let email = format!("rollback-{}@example.com", chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default());
+
+        let tx_email = email.clone();
+        let result: Result<(), AppError> = uow.transaction(move |tx| Box::pin(async move {
+            let user = tx.users().create(tx_email, "hash".to_string(), UserRole::User).await?;
+            tx.password_history().record(user.id, &user.password).await?;
+            Err(AppError::Conflict)
+        }))
+        .await;
+
+        assert!(matches!(result, Err(AppError::Conflict)));
+        let users = DieselUserRepository::new(pool);
+        assert!(users.find_by_email(&email).await.unwrap().is_none());
+    }
+}
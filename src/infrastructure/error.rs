@@ -1,10 +1,22 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde::Serialize;
+use diesel::result::DatabaseErrorKind;
 use thiserror::Error;
+use utoipa::ToSchema;
+use fluent_bundle::{FluentArgs, FluentValue};
+use unic_langid::LanguageIdentifier;
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+use crate::infrastructure::{auth::jwt::JwtError, i18n, request_context};
+
+// Seconds clients are asked to wait when the database is saturated
+const RETRY_AFTER_SECS: &str = "5";
+
+// Unique constraint created by `email VARCHAR(255) NOT NULL UNIQUE`
+const USERS_EMAIL_UNIQUE: &str = "users_email_key";
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -13,7 +25,13 @@ pub enum AppError {
     #[error("Not found")]
     NotFound,
     #[error("Database error")]
-    DatabaseError(#[from] diesel::result::Error),
+    DatabaseError(diesel::result::Error),
+    #[error("Conflicts with existing data")]
+    Conflict,
+    #[error("Constraint violation")]
+    ConstraintViolation,
+    #[error("Service unavailable")]
+    ServiceUnavailable,
     #[error("Internal server error")]
     InternalServerError,
     #[error("Rate limit exceeded")]
@@ -22,36 +40,244 @@ pub enum AppError {
     UserAlreadyExists,
     #[error("Invalid credentials")]
     InvalidCredentials,
-    #[error("Invalid email format")]
-    InvalidEmail,
     #[error("Invalid password format")]
     InvalidPassword,
+    #[error("Validation failed")]
+    ValidationError(#[from] ValidationErrors),
     #[error("Email not verified")]
     EmailNotVerified,
     #[error("Insufficient permissions")]
     InsufficientPermissions,
+    #[error("Invalid pagination cursor")]
+    InvalidCursor,
+    #[error("Precondition failed")]
+    PreconditionFailed,
+    #[error("Precondition required")]
+    PreconditionRequired,
+    #[error("Unsupported media type")]
+    UnsupportedMediaType,
+    #[error("Payload too large")]
+    PayloadTooLarge,
+    #[error("Request timed out")]
+    RequestTimeout,
+    #[error("Invalid idempotency key")]
+    InvalidIdempotencyKey,
+    #[error("Idempotency key reused with a different request")]
+    IdempotencyKeyReused,
+    #[error("Idempotency key in progress")]
+    IdempotencyKeyInProgress,
+}
+
+impl AppError {
+    // Status, stable machine-readable code and human-readable message.
+    // Codes are part of the API contract; never rename an existing one.
+    fn parts(&self) -> (StatusCode, &'static str, &'static str) {
+        match self {
+            AppError::AuthenticationError => (StatusCode::UNAUTHORIZED, "authentication_failed", "Authentication failed"),
+            AppError::NotFound => (StatusCode::NOT_FOUND, "not_found", "Resource not found"),
+            AppError::DatabaseError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "database_error", "Database error"),
+            AppError::Conflict => (StatusCode::CONFLICT, "conflict", "Request conflicts with existing data"),
+            AppError::ConstraintViolation => (StatusCode::UNPROCESSABLE_ENTITY, "constraint_violation", "Request violates a data constraint"),
+            AppError::ServiceUnavailable => (StatusCode::SERVICE_UNAVAILABLE, "service_unavailable", "Service temporarily unavailable"),
+            AppError::InternalServerError => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "Internal server error"),
+            AppError::RateLimitExceeded => (StatusCode::TOO_MANY_REQUESTS, "rate_limit_exceeded", "Rate limit exceeded"),
+            AppError::UserAlreadyExists => (StatusCode::CONFLICT, "user_already_exists", "User already exists"),
+            AppError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "invalid_credentials", "Invalid credentials"),
+            AppError::InvalidPassword => (StatusCode::BAD_REQUEST, "invalid_password", "Password does not meet requirements"),
+            AppError::ValidationError(_) => (StatusCode::UNPROCESSABLE_ENTITY, "validation_failed", "Request validation failed"),
+            AppError::EmailNotVerified => (StatusCode::FORBIDDEN, "email_not_verified", "Email not verified"),
+            AppError::InsufficientPermissions => (StatusCode::FORBIDDEN, "insufficient_permissions", "Insufficient permissions"),
+            AppError::InvalidCursor => (StatusCode::BAD_REQUEST, "invalid_cursor", "Invalid pagination cursor"),
+            AppError::PreconditionFailed => (StatusCode::PRECONDITION_FAILED, "precondition_failed", "Resource was modified by another request"),
+            AppError::PreconditionRequired => (StatusCode::PRECONDITION_REQUIRED, "precondition_required", "If-Match header is required"),
+            AppError::UnsupportedMediaType => (StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_media_type", "Unsupported media type"),
+            AppError::PayloadTooLarge => (StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", "Request body is too large"),
+            AppError::RequestTimeout => (StatusCode::REQUEST_TIMEOUT, "request_timeout", "Request took too long to process"),
+            AppError::InvalidIdempotencyKey => (StatusCode::BAD_REQUEST, "invalid_idempotency_key", "Idempotency-Key must be 1 to 255 visible ASCII characters"),
+            AppError::IdempotencyKeyReused => (StatusCode::UNPROCESSABLE_ENTITY, "idempotency_key_reused", "Idempotency-Key was already used for a different request"),
+            AppError::IdempotencyKeyInProgress => (StatusCode::CONFLICT, "idempotency_key_in_progress", "A request with this Idempotency-Key is still being processed"),
+        }
+    }
+}
+
+// Translates Diesel errors into domain errors, logging the underlying cause
+// since it never reaches the client.
+impl From<diesel::result::Error> for AppError {
+    fn from(err: diesel::result::Error) -> Self {
+        use diesel::result::Error;
+
+        match &err {
+            Error::NotFound => AppError::NotFound,
+            Error::DatabaseError(kind, info) => {
+                let constraint = info.constraint_name().unwrap_or("unknown");
+                match kind {
+                    DatabaseErrorKind::UniqueViolation if constraint == USERS_EMAIL_UNIQUE => {
+                        AppError::UserAlreadyExists
+                    }
+                    DatabaseErrorKind::UniqueViolation => {
+                        tracing::warn!(constraint, "Unique violation: {}", info.message());
+                        AppError::Conflict
+                    }
+                    DatabaseErrorKind::ForeignKeyViolation => {
+                        tracing::warn!(constraint, "Foreign key violation: {}", info.message());
+                        AppError::Conflict
+                    }
+                    DatabaseErrorKind::CheckViolation | DatabaseErrorKind::NotNullViolation => {
+                        tracing::warn!(constraint, "Constraint violation: {}", info.message());
+                        AppError::ConstraintViolation
+                    }
+                    DatabaseErrorKind::SerializationFailure => {
+                        tracing::warn!("Serialization failure: {}", info.message());
+                        AppError::ServiceUnavailable
+                    }
+                    DatabaseErrorKind::ClosedConnection => {
+                        tracing::error!("Database connection closed: {}", info.message());
+                        AppError::ServiceUnavailable
+                    }
+                    _ => {
+                        tracing::error!("Database error: {}", info.message());
+                        AppError::DatabaseError(err)
+                    }
+                }
+            }
+            _ => {
+                tracing::error!("Database error: {}", err);
+                AppError::DatabaseError(err)
+            }
+        }
+    }
+}
+
+impl From<JwtError> for AppError {
+    fn from(err: JwtError) -> Self {
+        match err {
+            JwtError::TokenCreation => AppError::InternalServerError,
+            JwtError::TokenVerification => AppError::AuthenticationError,
+        }
+    }
+}
+
+// r2d2 only fails to hand out a connection once its checkout timeout elapses,
+// i.e. the pool is exhausted or the database is unreachable.
+impl From<diesel::r2d2::PoolError> for AppError {
+    fn from(err: diesel::r2d2::PoolError) -> Self {
+        tracing::error!("Failed to get database connection: {}", err);
+        AppError::ServiceUnavailable
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: Option<String>,
+}
+
+// Looks up `validation-<field>-<code>`, then `validation-<code>`, passing the
+// validator params (e.g. `min`) as arguments. Falls back to the message on the
+// validation attribute.
+fn field_message(locale: &LanguageIdentifier, field: &str, err: &ValidationError) -> Option<String> {
+    let mut args = FluentArgs::new();
+    for (name, value) in &err.params {
+        match value {
+            serde_json::Value::Number(n) => {
+                if let Some(n) = n.as_f64() {
+                    args.set(name.to_string(), FluentValue::from(n));
+                }
+            }
+            serde_json::Value::String(v) => args.set(name.to_string(), FluentValue::from(v.clone())),
+            _ => {}
+        }
+    }
+
+    i18n::translate(locale, &format!("validation-{}-{}", field, err.code), Some(&args))
+        .or_else(|| i18n::translate(locale, &format!("validation-{}", err.code), Some(&args)))
+        .or_else(|| err.message.as_ref().map(|m| m.to_string()))
+}
+
+// Flattens nested validator errors into dotted field paths, e.g. `address.city`
+// or `emails[2]`, with messages in the current request locale.
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    fn collect(prefix: &str, errors: &ValidationErrors, out: &mut Vec<FieldError>) {
+        let locale = request_context::locale();
+
+        for (field, kind) in errors.errors() {
+            let path = if prefix.is_empty() {
+                field.to_string()
+            } else {
+                format!("{}.{}", prefix, field)
+            };
+
+            match kind {
+                ValidationErrorsKind::Field(errs) => {
+                    out.extend(errs.iter().map(|err| FieldError {
+                        field: path.clone(),
+                        code: err.code.to_string(),
+                        message: field_message(&locale, field, err),
+                    }));
+                }
+                ValidationErrorsKind::Struct(nested) => collect(&path, nested, out),
+                ValidationErrorsKind::List(items) => {
+                    for (idx, nested) in items {
+                        collect(&format!("{}[{}]", path, idx), nested, out);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    collect("", errors, &mut out);
+    out
+}
+
+// RFC 7807 problem details body, served as `application/problem+json`
+#[derive(Serialize, ToSchema)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    problem_type: &'static str,
+    title: &'static str,
+    status: u16,
+    detail: String,
+    // Stable machine-readable error code, e.g. `user_already_exists`
+    code: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FieldError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            AppError::AuthenticationError => (StatusCode::UNAUTHORIZED, "Authentication failed"),
-            AppError::NotFound => (StatusCode::NOT_FOUND, "Resource not found"),
-            AppError::DatabaseError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error"),
-            AppError::InternalServerError => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"),
-            AppError::RateLimitExceeded => (StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded"),
-            AppError::UserAlreadyExists => (StatusCode::CONFLICT, "User already exists"),
-            AppError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "Invalid credentials"),
-            AppError::InvalidEmail => (StatusCode::BAD_REQUEST, "Invalid email format"),
-            AppError::InvalidPassword => (StatusCode::BAD_REQUEST, "Password does not meet requirements"),
-            AppError::EmailNotVerified => (StatusCode::FORBIDDEN, "Email not verified"),
-            AppError::InsufficientPermissions => (StatusCode::FORBIDDEN, "Insufficient permissions"),
+        let (status, code, message) = self.parts();
+
+        let errors = match &self {
+            AppError::ValidationError(e) => field_errors(e),
+            _ => Vec::new(),
         };
 
-        let body = Json(json!({
-            "error": error_message
-        }));
+        let problem = Problem {
+            problem_type: "about:blank",
+            title: status.canonical_reason().unwrap_or("Error"),
+            status: status.as_u16(),
+            detail: i18n::translate(&request_context::locale(), &format!("error-{}", code), None)
+                .unwrap_or_else(|| message.to_string()),
+            code,
+            errors,
+            request_id: request_context::request_id(),
+        };
 
-        (status, body).into_response()
+        let mut response = (status, Json(problem)).into_response();
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        if status == StatusCode::SERVICE_UNAVAILABLE {
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from_static(RETRY_AFTER_SECS),
+            );
+        }
+        response
     }
 } 
\ No newline at end of file
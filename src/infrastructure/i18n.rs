@@ -0,0 +1,157 @@
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+// Message catalogs shared by API errors, validation messages and emails.
+// The first entry is the fallback for missing locales and keys.
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../../locales/en/main.ftl")),
+    ("de", include_str!("../../locales/de/main.ftl")),
+    ("es", include_str!("../../locales/es/main.ftl")),
+];
+
+lazy_static::lazy_static! {
+    static ref BUNDLES: Vec<(LanguageIdentifier, FluentBundle<FluentResource>)> = CATALOGS
+        .iter()
+        .map(|(locale, source)| {
+            let langid: LanguageIdentifier = locale.parse().expect("Invalid catalog locale");
+            let resource = FluentResource::try_new(source.to_string())
+                .unwrap_or_else(|_| panic!("Invalid message catalog for {}", locale));
+
+            let mut bundle = FluentBundle::new_concurrent(vec![langid.clone()]);
+            // Unicode isolation marks around arguments only confuse API clients
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .unwrap_or_else(|_| panic!("Duplicate message ids in catalog for {}", locale));
+
+            (langid, bundle)
+        })
+        .collect();
+}
+
+pub fn default_locale() -> LanguageIdentifier {
+    DEFAULT_LOCALE.parse().expect("Invalid default locale")
+}
+
+fn supported(requested: &LanguageIdentifier) -> Option<LanguageIdentifier> {
+    BUNDLES
+        .iter()
+        .map(|(langid, _)| langid)
+        .find(|langid| *langid == requested)
+        .or_else(|| {
+            BUNDLES
+                .iter()
+                .map(|(langid, _)| langid)
+                .find(|langid| langid.language == requested.language)
+        })
+        .cloned()
+}
+
+// Picks the best supported locale from an explicit preference, then the
+// `Accept-Language` header (honouring q-values), then the default.
+pub fn negotiate(preferred: Option<&str>, accept_language: Option<&str>) -> LanguageIdentifier {
+    if let Some(langid) = preferred
+        .and_then(|p| p.parse::<LanguageIdentifier>().ok())
+        .and_then(|langid| supported(&langid))
+    {
+        return langid;
+    }
+
+    let mut ranges: Vec<(LanguageIdentifier, f32)> = accept_language
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.trim().split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .and_then(|q| q.parse().ok())
+                .unwrap_or(1.0);
+            Some((tag.parse().ok()?, quality))
+        })
+        .filter(|(_, quality)| *quality > 0.0)
+        .collect();
+
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    ranges
+        .iter()
+        .find_map(|(langid, _)| supported(langid))
+        .unwrap_or_else(default_locale)
+}
+
+fn format(bundle: &FluentBundle<FluentResource>, key: &str, args: Option<&FluentArgs>) -> Option<String> {
+    let pattern = bundle.get_message(key)?.value()?;
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, args, &mut errors);
+
+    if !errors.is_empty() {
+        tracing::warn!("Failed to format message {}: {:?}", key, errors);
+    }
+    Some(text.into_owned())
+}
+
+// Looks `key` up in `locale`, falling back to the default catalog
+pub fn translate(locale: &LanguageIdentifier, key: &str, args: Option<&FluentArgs>) -> Option<String> {
+    BUNDLES
+        .iter()
+        .find(|(langid, _)| langid == locale)
+        .and_then(|(_, bundle)| format(bundle, key, args))
+        .or_else(|| format(&BUNDLES[0].1, key, args))
+}
+
+pub struct LocalizedEmail {
+    pub subject: String,
+    pub body: String,
+}
+
+// Renders the `email-<template>-subject` / `email-<template>-body` pair
+pub fn email(locale: &LanguageIdentifier, template: &str, args: &FluentArgs) -> Option<LocalizedEmail> {
+    Some(LocalizedEmail {
+        subject: translate(locale, &format!("email-{}-subject", template), Some(args))?,
+        body: translate(locale, &format!("email-{}-body", template), Some(args))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn negotiated(preferred: Option<&str>, accept_language: Option<&str>) -> String {
+        negotiate(preferred, accept_language).to_string()
+    }
+
+    #[test]
+    fn negotiate_orders_by_q_value() {
+        assert_eq!(negotiated(None, Some("en;q=0.5, es;q=0.9, de;q=0.7")), "es");
+        // Ranges without a q-value count as 1.0
+        assert_eq!(negotiated(None, Some("es;q=0.8, de")), "de");
+    }
+
+    #[test]
+    fn negotiate_skips_unsupported_and_refused_locales() {
+        assert_eq!(negotiated(None, Some("fr, de;q=0, es;q=0.1")), "es");
+        assert_eq!(negotiated(None, Some("de-AT")), "de");
+        assert_eq!(negotiated(None, Some("fr, ja")), DEFAULT_LOCALE);
+        assert_eq!(negotiated(None, None), DEFAULT_LOCALE);
+    }
+
+    #[test]
+    fn explicit_preference_wins_when_supported() {
+        assert_eq!(negotiated(Some("de"), Some("es")), "de");
+        assert_eq!(negotiated(Some("fr"), Some("es")), "es");
+    }
+
+    #[test]
+    fn emails_render_from_the_negotiated_catalog() {
+        let mut args = FluentArgs::new();
+        args.set("link", "https://app.example.com/verify-email?token=t");
+
+        let german = email(&negotiate(Some("de"), None), "verification", &args).unwrap();
+        assert_eq!(german.subject, "Bestätigen Sie Ihre E-Mail-Adresse");
+        assert!(german.body.contains("https://app.example.com/verify-email?token=t"));
+        assert!(email(&default_locale(), "missing", &args).is_none());
+    }
+} 
\ No newline at end of file
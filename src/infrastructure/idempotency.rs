@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use diesel::prelude::*;
+use diesel::sql_types::{Binary, Double, Nullable, SmallInt, Text};
+use crate::infrastructure::{config::database::DbPool, error::AppError, metrics};
+
+// A first request that hasn't completed within this is assumed lost (e.g.
+// the instance died), and the key may be used again
+pub const IN_FLIGHT_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum Begin {
+    // The caller owns the key and must `complete` or `abandon` it
+    Started,
+    InFlight,
+    Replay(StoredResponse),
+    // Same key, different request
+    Mismatch,
+}
+
+// First response per idempotency key. Blocking; call from `spawn_blocking`
+// when the store does I/O.
+pub trait IdempotencyStore: Send + Sync {
+    fn begin(&self, key: &str, fingerprint: &str) -> Result<Begin, AppError>;
+
+    fn complete(&self, key: &str, response: &StoredResponse, ttl: Duration) -> Result<(), AppError>;
+
+    // Releases the key so a retry runs the request again
+    fn abandon(&self, key: &str) -> Result<(), AppError>;
+
+    fn prune(&self) -> Result<usize, AppError>;
+}
+
+struct Entry {
+    fingerprint: String,
+    response: Option<StoredResponse>,
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl IdempotencyStore for MemoryStore {
+    fn begin(&self, key: &str, fingerprint: &str) -> Result<Begin, AppError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+
+        match entries.get(key) {
+            Some(entry) if entry.expires_at > now => Ok(if entry.fingerprint != fingerprint {
+                Begin::Mismatch
+            } else {
+                entry.response.clone().map_or(Begin::InFlight, Begin::Replay)
+            }),
+            _ => {
+                entries.insert(key.to_string(), Entry {
+                    fingerprint: fingerprint.to_string(),
+                    response: None,
+                    expires_at: now + IN_FLIGHT_TIMEOUT,
+                });
+                Ok(Begin::Started)
+            }
+        }
+    }
+
+    fn complete(&self, key: &str, response: &StoredResponse, ttl: Duration) -> Result<(), AppError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(entry) = entries.get_mut(key) {
+            entry.response = Some(response.clone());
+            entry.expires_at = Instant::now() + ttl;
+        }
+        Ok(())
+    }
+
+    fn abandon(&self, key: &str) -> Result<(), AppError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        entries.remove(key);
+        Ok(())
+    }
+
+    fn prune(&self) -> Result<usize, AppError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = entries.len();
+        let now = Instant::now();
+        entries.retain(|_, entry| entry.expires_at > now);
+        Ok(before - entries.len())
+    }
+}
+
+#[derive(QueryableByName)]
+struct KeyRow {
+    #[diesel(sql_type = Text)]
+    fingerprint: String,
+    #[diesel(sql_type = Nullable<SmallInt>)]
+    status: Option<i16>,
+    #[diesel(sql_type = Nullable<Text>)]
+    headers: Option<String>,
+    #[diesel(sql_type = Nullable<Binary>)]
+    body: Option<Vec<u8>>,
+}
+
+// Keys in the `idempotency_keys` table. Claiming a key is a single upsert
+// that only succeeds for new or expired keys, so two instances can't both
+// run the same request.
+pub struct PostgresStore {
+    pool: DbPool,
+}
+
+impl PostgresStore {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+const CLAIM_SQL: &str = "
+    INSERT INTO idempotency_keys AS k (key, fingerprint, expires_at)
+    VALUES ($1, $2, clock_timestamp() + make_interval(secs => $3))
+    ON CONFLICT (key) DO UPDATE SET
+        fingerprint = EXCLUDED.fingerprint,
+        status = NULL,
+        headers = NULL,
+        body = NULL,
+        expires_at = EXCLUDED.expires_at
+    WHERE k.expires_at <= clock_timestamp()";
+
+impl IdempotencyStore for PostgresStore {
+    fn begin(&self, key: &str, fingerprint: &str) -> Result<Begin, AppError> {
+        let mut conn = metrics::timed_get(&self.pool, "primary").map_err(AppError::from)?;
+        let claimed = diesel::sql_query(CLAIM_SQL)
+            .bind::<Text, _>(key)
+            .bind::<Text, _>(fingerprint)
+            .bind::<Double, _>(IN_FLIGHT_TIMEOUT.as_secs_f64())
+            .execute(&mut conn)
+            .map_err(AppError::from)?;
+        if claimed == 1 {
+            return Ok(Begin::Started);
+        }
+
+        let row: Option<KeyRow> = diesel::sql_query(
+            "SELECT fingerprint, status, headers, body FROM idempotency_keys WHERE key = $1",
+        )
+        .bind::<Text, _>(key)
+        .get_result(&mut conn)
+        .optional()
+        .map_err(AppError::from)?;
+
+        // Pruned in between; the client can simply retry
+        let Some(row) = row else {
+            return Ok(Begin::InFlight);
+        };
+        if row.fingerprint != fingerprint {
+            return Ok(Begin::Mismatch);
+        }
+        Ok(match row.status {
+            None => Begin::InFlight,
+            Some(status) => Begin::Replay(StoredResponse {
+                status: status as u16,
+                headers: row
+                    .headers
+                    .and_then(|headers| serde_json::from_str(&headers).ok())
+                    .unwrap_or_default(),
+                body: row.body.unwrap_or_default(),
+            }),
+        })
+    }
+
+    fn complete(&self, key: &str, response: &StoredResponse, ttl: Duration) -> Result<(), AppError> {
+        let mut conn = metrics::timed_get(&self.pool, "primary").map_err(AppError::from)?;
+        let headers = serde_json::to_string(&response.headers).map_err(|_| AppError::InternalServerError)?;
+        diesel::sql_query(
+            "UPDATE idempotency_keys
+             SET status = $2, headers = $3, body = $4, expires_at = clock_timestamp() + make_interval(secs => $5)
+             WHERE key = $1",
+        )
+        .bind::<Text, _>(key)
+        .bind::<SmallInt, _>(response.status as i16)
+        .bind::<Text, _>(headers)
+        .bind::<Binary, _>(&response.body)
+        .bind::<Double, _>(ttl.as_secs_f64())
+        .execute(&mut conn)
+        .map(|_| ())
+        .map_err(AppError::from)
+    }
+
+    fn abandon(&self, key: &str) -> Result<(), AppError> {
+        let mut conn = metrics::timed_get(&self.pool, "primary").map_err(AppError::from)?;
+        diesel::sql_query("DELETE FROM idempotency_keys WHERE key = $1 AND status IS NULL")
+            .bind::<Text, _>(key)
+            .execute(&mut conn)
+            .map(|_| ())
+            .map_err(AppError::from)
+    }
+
+    fn prune(&self) -> Result<usize, AppError> {
+        let mut conn = metrics::timed_get(&self.pool, "primary").map_err(AppError::from)?;
+        diesel::sql_query("DELETE FROM idempotency_keys WHERE expires_at <= clock_timestamp()")
+            .execute(&mut conn)
+            .map_err(AppError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str) -> StoredResponse {
+        StoredResponse {
+            status: 201,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn memory_store_replays_completed_requests() {
+        let store = MemoryStore::default();
+
+        assert!(matches!(store.begin("key-1", "fp-a").unwrap(), Begin::Started));
+        assert!(matches!(store.begin("key-1", "fp-a").unwrap(), Begin::InFlight));
+        assert!(matches!(store.begin("key-1", "fp-b").unwrap(), Begin::Mismatch));
+
+        store.complete("key-1", &response("{\"id\":1}"), Duration::from_secs(60)).unwrap();
+        match store.begin("key-1", "fp-a").unwrap() {
+            Begin::Replay(stored) => assert_eq!(stored, response("{\"id\":1}")),
+            other => panic!("expected a replay, got {:?}", other),
+        }
+        assert!(matches!(store.begin("key-1", "fp-b").unwrap(), Begin::Mismatch));
+        assert!(matches!(store.begin("key-2", "fp-a").unwrap(), Begin::Started));
+    }
+
+    #[test]
+    fn memory_store_releases_abandoned_and_expired_keys() {
+        let store = MemoryStore::default();
+
+        store.begin("key-1", "fp-a").unwrap();
+        store.abandon("key-1").unwrap();
+        // A retry may even change the request once the key is released
+        assert!(matches!(store.begin("key-1", "fp-b").unwrap(), Begin::Started));
+
+        store.complete("key-1", &response("{}"), Duration::ZERO).unwrap();
+        store.begin("key-2", "fp-a").unwrap();
+        assert_eq!(store.prune().unwrap(), 1);
+        assert!(matches!(store.begin("key-1", "fp-a").unwrap(), Begin::Started));
+        assert!(matches!(store.begin("key-2", "fp-a").unwrap(), Begin::InFlight));
+    }
+} 
\ No newline at end of file
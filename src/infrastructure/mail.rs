@@ -0,0 +1,93 @@
+use std::sync::Arc;
+use fluent_bundle::FluentArgs;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use thiserror::Error;
+use tracing::instrument;
+use unic_langid::LanguageIdentifier;
+use crate::infrastructure::{config::app::MailConfig, i18n};
+
+#[derive(Error, Debug)]
+pub enum MailError {
+    #[error("Missing email template `{0}`")]
+    MissingTemplate(&'static str),
+    #[error("Invalid email address")]
+    InvalidAddress,
+    #[error("Failed to build email: {0}")]
+    Build(#[from] lettre::error::Error),
+    #[error("Failed to send email: {0}")]
+    Send(#[from] lettre::transport::smtp::Error),
+}
+
+struct Smtp {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    verification_url: String,
+}
+
+// Sends the localized emails rendered from the shared message catalogs.
+// Without `mail.smtp_url` nothing is sent.
+#[derive(Clone)]
+pub struct Mailer {
+    smtp: Option<Arc<Smtp>>,
+}
+
+impl Mailer {
+    pub fn new(config: &MailConfig) -> Result<Self, String> {
+        let Some(url) = &config.smtp_url else {
+            return Ok(Self { smtp: None });
+        };
+
+        // Presence of both is checked by `AppConfig::validate`
+        let from = config
+            .from_address
+            .as_deref()
+            .unwrap_or_default()
+            .parse::<Mailbox>()
+            .map_err(|e| format!("mail.from_address: {}", e))?;
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(url.expose())
+            .map_err(|e| format!("mail.smtp_url: {}", e))?
+            .build();
+
+        Ok(Self {
+            smtp: Some(Arc::new(Smtp {
+                transport,
+                from,
+                verification_url: config.verification_url.clone().unwrap_or_default(),
+            })),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.smtp.is_some()
+    }
+
+    #[instrument(name = "mail.send_verification", skip_all, fields(locale = %locale))]
+    pub async fn send_verification(
+        &self,
+        locale: &LanguageIdentifier,
+        to: &str,
+        token: &str,
+    ) -> Result<(), MailError> {
+        let Some(smtp) = &self.smtp else {
+            return Ok(());
+        };
+
+        let mut args = FluentArgs::new();
+        args.set("link", format!("{}?token={}", smtp.verification_url, token));
+        let email = i18n::email(locale, "verification", &args)
+            .ok_or(MailError::MissingTemplate("verification"))?;
+
+        let message = Message::builder()
+            .from(smtp.from.clone())
+            .to(to.parse().map_err(|_| MailError::InvalidAddress)?)
+            .subject(email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body)?;
+
+        smtp.transport.send(message).await?;
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,84 @@
+use std::time::Instant;
+use diesel::r2d2::PoolError;
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge_vec,
+    Encoder, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, TextEncoder,
+};
+use crate::infrastructure::{
+    config::database::DbPool,
+    db::{connection::PgPooledConnection, replica::DbRouter},
+};
+
+lazy_static! {
+    pub static ref HTTP_REQUESTS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "http_requests_total",
+        "HTTP requests handled, by route template and status",
+        &["method", "route", "status"]
+    )
+    .unwrap();
+    pub static ref HTTP_REQUEST_DURATION_SECONDS: HistogramVec = register_histogram_vec!(
+        "http_request_duration_seconds",
+        "HTTP request latency, by route template and status",
+        &["method", "route", "status"]
+    )
+    .unwrap();
+    static ref DB_POOL_CONNECTIONS: IntGaugeVec = register_int_gauge_vec!(
+        "db_pool_connections",
+        "Database pool connections, by pool and state (idle or in_use)",
+        &["pool", "state"]
+    )
+    .unwrap();
+    static ref DB_POOL_WAIT_SECONDS: HistogramVec = register_histogram_vec!(
+        "db_pool_wait_seconds",
+        "Time spent waiting to check a connection out of a pool",
+        &["pool"],
+        vec![0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 30.0]
+    )
+    .unwrap();
+    pub static ref LOGINS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "auth_logins_total",
+        "Login attempts, by outcome (success or failure)",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref TOKENS_ISSUED_TOTAL: IntCounter = register_int_counter!(
+        "auth_tokens_issued_total",
+        "JWTs issued"
+    )
+    .unwrap();
+    pub static ref RATE_LIMIT_BACKEND_ERRORS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "rate_limit_backend_errors_total",
+        "Rate limit checks that failed, by what was done with the request (allowed or rejected)",
+        &["action"]
+    )
+    .unwrap();
+}
+
+// Checks a connection out of `pool`, recording how long that took
+pub fn timed_get(pool: &DbPool, name: &str) -> Result<PgPooledConnection, PoolError> {
+    let started = Instant::now();
+    let conn = pool.get();
+    DB_POOL_WAIT_SECONDS
+        .with_label_values(&[name])
+        .observe(started.elapsed().as_secs_f64());
+    conn
+}
+
+// Refreshes the pool gauges and encodes every metric in the Prometheus text
+// format
+pub fn render(router: &DbRouter) -> String {
+    for (name, pool) in router.pools() {
+        let state = pool.state();
+        DB_POOL_CONNECTIONS
+            .with_label_values(&[&name, "idle"])
+            .set(state.idle_connections as i64);
+        DB_POOL_CONNECTIONS
+            .with_label_values(&[&name, "in_use"])
+            .set((state.connections - state.idle_connections) as i64);
+    }
+
+    let mut buffer = Vec::new();
+    let _ = TextEncoder::new().encode(&prometheus::gather(), &mut buffer);
+    String::from_utf8(buffer).unwrap_or_default()
+} 
\ No newline at end of file
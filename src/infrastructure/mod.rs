@@ -1,6 +1,18 @@
 pub mod auth;
+pub mod cli;
 pub mod config;
 pub mod db;
+pub mod health;
+pub mod i18n;
+pub mod idempotency;
+pub mod mail;
+pub mod metrics;
+pub mod rate_limit;
 pub mod repositories;
+pub mod request_context;
+pub mod secrets;
 pub mod server;
+pub mod shutdown;
+pub mod telemetry;
+pub mod tls;
 pub mod error; 
\ No newline at end of file
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Double, Text};
+use crate::infrastructure::{config::database::DbPool, error::AppError, metrics};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    pub capacity: u64,
+    pub period: Duration,
+}
+
+impl Quota {
+    fn refill_per_sec(&self) -> f64 {
+        self.capacity as f64 / self.period.as_secs_f64()
+    }
+
+    fn decision(&self, tokens: f64, allowed: bool) -> Decision {
+        let rate = self.refill_per_sec();
+        Decision {
+            allowed,
+            remaining: tokens.max(0.0).floor() as u64,
+            reset_after: Duration::from_secs_f64(((self.capacity as f64 - tokens) / rate).max(0.0)),
+            retry_after: (!allowed).then(|| Duration::from_secs_f64(((1.0 - tokens) / rate).max(0.0))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Decision {
+    pub allowed: bool,
+    pub remaining: u64,
+    // Until the bucket is full again
+    pub reset_after: Duration,
+    // Until the next request would be allowed, when this one wasn't
+    pub retry_after: Option<Duration>,
+}
+
+// Token buckets keyed by client and policy. Blocking; call from
+// `spawn_blocking` when the store does I/O.
+pub trait RateLimitStore: Send + Sync {
+    fn acquire(&self, key: &str, quota: Quota) -> Result<Decision, AppError>;
+
+    // Forgets buckets untouched for `idle`; they would be full by now anyway
+    fn prune(&self, idle: Duration) -> Result<usize, AppError>;
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl RateLimitStore for MemoryStore {
+    fn acquire(&self, key: &str, quota: Quota) -> Result<Decision, AppError> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        let (tokens, last) = buckets
+            .entry(key.to_string())
+            .or_insert((quota.capacity as f64, now));
+
+        let refilled = (*tokens + now.duration_since(*last).as_secs_f64() * quota.refill_per_sec())
+            .min(quota.capacity as f64);
+        let allowed = refilled >= 1.0;
+        *tokens = if allowed { refilled - 1.0 } else { refilled };
+        *last = now;
+
+        Ok(quota.decision(*tokens, allowed))
+    }
+
+    fn prune(&self, idle: Duration) -> Result<usize, AppError> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = buckets.len();
+        buckets.retain(|_, (_, last)| last.elapsed() < idle);
+        Ok(before - buckets.len())
+    }
+}
+
+#[derive(QueryableByName)]
+struct BucketRow {
+    #[diesel(sql_type = Double)]
+    tokens: f64,
+    #[diesel(sql_type = Bool)]
+    allowed: bool,
+}
+
+// Buckets in the `rate_limit_buckets` table, refilled and spent in a single
+// upsert so concurrent instances never double-spend a token
+pub struct PostgresStore {
+    pool: DbPool,
+}
+
+impl PostgresStore {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+const ACQUIRE_SQL: &str = "
+    INSERT INTO rate_limit_buckets AS b (key, tokens, allowed, updated_at)
+    VALUES ($1, $2 - 1, true, clock_timestamp())
+    ON CONFLICT (key) DO UPDATE SET
+        tokens = CASE
+            WHEN LEAST($2, b.tokens + EXTRACT(EPOCH FROM clock_timestamp() - b.updated_at) * $3) >= 1
+            THEN LEAST($2, b.tokens + EXTRACT(EPOCH FROM clock_timestamp() - b.updated_at) * $3) - 1
+            ELSE LEAST($2, b.tokens + EXTRACT(EPOCH FROM clock_timestamp() - b.updated_at) * $3)
+        END,
+        allowed = LEAST($2, b.tokens + EXTRACT(EPOCH FROM clock_timestamp() - b.updated_at) * $3) >= 1,
+        updated_at = clock_timestamp()
+    RETURNING tokens, allowed";
+
+impl RateLimitStore for PostgresStore {
+    fn acquire(&self, key: &str, quota: Quota) -> Result<Decision, AppError> {
+        let mut conn = metrics::timed_get(&self.pool, "primary").map_err(AppError::from)?;
+        let row: BucketRow = diesel::sql_query(ACQUIRE_SQL)
+            .bind::<Text, _>(key)
+            .bind::<Double, _>(quota.capacity as f64)
+            .bind::<Double, _>(quota.refill_per_sec())
+            .get_result(&mut conn)
+            .map_err(AppError::from)?;
+
+        Ok(quota.decision(row.tokens, row.allowed))
+    }
+
+    fn prune(&self, idle: Duration) -> Result<usize, AppError> {
+        let mut conn = metrics::timed_get(&self.pool, "primary").map_err(AppError::from)?;
+        diesel::sql_query("DELETE FROM rate_limit_buckets WHERE updated_at < clock_timestamp() - make_interval(secs => $1)")
+            .bind::<Double, _>(idle.as_secs_f64())
+            .execute(&mut conn)
+            .map_err(AppError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(duration: Duration) -> f64 {
+        duration.as_secs_f64()
+    }
+
+    #[test]
+    fn decision_reports_remaining_tokens_and_reset() {
+        // One token per second
+        let quota = Quota { capacity: 10, period: Duration::from_secs(10) };
+
+        let allowed = quota.decision(4.5, true);
+        assert!(allowed.allowed);
+        assert_eq!(allowed.remaining, 4);
+        assert!((secs(allowed.reset_after) - 5.5).abs() < 1e-9);
+        assert!(allowed.retry_after.is_none());
+
+        let denied = quota.decision(0.25, false);
+        assert!(!denied.allowed);
+        assert_eq!(denied.remaining, 0);
+        assert!((secs(denied.reset_after) - 9.75).abs() < 1e-9);
+        assert!((secs(denied.retry_after.unwrap()) - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn memory_store_spends_and_denies_per_key() {
+        let store = MemoryStore::default();
+        // Refills one token every 20 minutes, so none come back mid-test
+        let quota = Quota { capacity: 3, period: Duration::from_secs(3600) };
+
+        let remaining: Vec<u64> = (0..3).map(|_| store.acquire("client-a", quota).unwrap().remaining).collect();
+        assert_eq!(remaining, vec![2, 1, 0]);
+
+        let denied = store.acquire("client-a", quota).unwrap();
+        assert!(!denied.allowed);
+        assert!(secs(denied.retry_after.unwrap()) > 1190.0);
+
+        assert!(store.acquire("client-b", quota).unwrap().allowed);
+    }
+
+    #[test]
+    fn memory_store_prunes_idle_buckets() {
+        let store = MemoryStore::default();
+        let quota = Quota { capacity: 1, period: Duration::from_secs(60) };
+        store.acquire("client-a", quota).unwrap();
+        store.acquire("client-b", quota).unwrap();
+
+        assert_eq!(store.prune(Duration::from_secs(3600)).unwrap(), 0);
+        assert_eq!(store.prune(Duration::ZERO).unwrap(), 2);
+        // A pruned bucket starts full again
+        assert!(store.acquire("client-a", quota).unwrap().allowed);
+    }
+} 
\ No newline at end of file
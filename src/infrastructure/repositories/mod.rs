@@ -0,0 +1,2 @@
+pub mod password_history_repository;
+pub mod user_repository;
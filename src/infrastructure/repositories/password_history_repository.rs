@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use tracing::instrument;
+use crate::{
+    domain::repositories::password_history_repository::PasswordHistoryRepository,
+    infrastructure::{db::connection::ConnectionSource, error::AppError},
+};
+
+#[derive(Clone)]
+pub struct DieselPasswordHistoryRepository {
+    source: ConnectionSource,
+}
+
+impl DieselPasswordHistoryRepository {
+    pub fn with_source(source: ConnectionSource) -> Self {
+        Self { source }
+    }
+}
+
+// Shared with `DieselUserRepository`, which records history inside its own
+// update transactions
+pub(crate) fn insert(conn: &mut PgConnection, user_id_val: i32, hash_val: &str) -> Result<(), AppError> {
+    use crate::schema::password_history::dsl::*;
+
+    diesel::insert_into(password_history)
+        .values((user_id.eq(user_id_val), password_hash.eq(hash_val)))
+        .execute(conn)
+        .map(|_| ())
+        .map_err(AppError::from)
+}
+
+pub(crate) fn recent(conn: &mut PgConnection, user_id_val: i32, limit: i64) -> Result<Vec<String>, AppError> {
+    use crate::schema::password_history::dsl::*;
+
+    password_history
+        .filter(user_id.eq(user_id_val))
+        .order(created_at.desc())
+        .limit(limit)
+        .select(password_hash)
+        .load(conn)
+        .map_err(AppError::from)
+}
+
+#[async_trait]
+impl PasswordHistoryRepository for DieselPasswordHistoryRepository {
+    #[instrument(name = "PasswordHistoryRepository.record", skip_all, fields(db.system = "postgresql", user.id = user_id))]
+    async fn record(&self, user_id: i32, password_hash: &str) -> Result<(), AppError> {
+        self.source.run(|conn| insert(conn, user_id, password_hash))
+    }
+} 
\ No newline at end of file
@@ -1,177 +1,440 @@
 use async_trait::async_trait;
-use bcrypt::{hash, DEFAULT_COST};
-use diesel::prelude::*;
+use diesel::{dsl::sql, prelude::*, sql_types::{Bool, Text}};
 use crate::{
     domain::{
-        models::user::User,
+        models::password_policy::PasswordPolicy,
+        models::user::{UpsertOutcome, User, UserRole},
+        models::user_query::{SortDirection, UserCursor, UserListQuery, UserSortField},
+        models::user_search::UserSearchHit,
         repositories::user_repository::UserRepository,
     },
     infrastructure::{
+        auth::password::{hash_password, verify_password},
         error::AppError,
         config::database::DbPool,
+        db::{connection::ConnectionSource, replica::DbRouter},
+        repositories::password_history_repository,
     },
 };
+use std::sync::Arc;
+use tracing::instrument;
 use validator::Validate;
 
+// Minimum pg_trgm similarity for a fuzzy (non-substring) match, applied by
+// the `%` operator so the trigram GIN indexes can serve it
+const SEARCH_SIMILARITY_THRESHOLD: f32 = 0.3;
+
+diesel::define_sql_function!(fn similarity(a: diesel::sql_types::Text, b: diesel::sql_types::Text) -> diesel::sql_types::Float4);
+diesel::define_sql_function!(fn greatest(a: diesel::sql_types::Float4, b: diesel::sql_types::Float4) -> diesel::sql_types::Float4);
+diesel::infix_operator!(TrigramMatches, " % ", backend: diesel::pg::Pg);
+diesel::define_sql_function!(fn coalesce(a: diesel::sql_types::Nullable<diesel::sql_types::Text>, b: diesel::sql_types::Text) -> diesel::sql_types::Text);
+
+#[derive(AsChangeset)]
+#[diesel(table_name = crate::schema::users)]
+struct UserChanges {
+    email: Option<String>,
+    password: Option<String>,
+    role: Option<UserRole>,
+    updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Clone)]
 pub struct DieselUserRepository {
-    pool: DbPool,
+    source: ConnectionSource,
+    policy: Arc<PasswordPolicy>,
 }
 
 impl DieselUserRepository {
     pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+        Self::with_source(ConnectionSource::Pool(pool))
     }
 
-    fn validate_password(&self, password: &str) -> Result<(), AppError> {
-        let requirements = PasswordRequirements {
-            password: password.to_string(),
-        };
+    pub fn with_router(router: Arc<DbRouter>) -> Self {
+        Self::with_source(ConnectionSource::Routed(router))
+    }
+
+    pub fn with_source(source: ConnectionSource) -> Self {
+        Self {
+            source,
+            policy: Arc::new(PasswordPolicy::default()),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: Arc<PasswordPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    // Rejects a password matching any of the user's last `history_size` hashes
+    // Every comparison is a full bcrypt verify, so they run off the executor
+    async fn check_password_history(&self, user_id: i32, password_val: &str) -> Result<(), AppError> {
+        let history_size = self.policy.history_size;
+        if history_size <= 0 {
+            return Ok(());
+        }
 
-        requirements.validate().map_err(|_| AppError::InvalidPassword)?;
+        let recent = self.source.run(|conn| password_history_repository::recent(conn, user_id, history_size))?;
+        let password_val = password_val.to_string();
+        let reused = tokio::task::spawn_blocking(move || {
+            recent.iter().any(|old| verify_password(&password_val, old).unwrap_or(false))
+        })
+        .await
+        .map_err(|_| AppError::InternalServerError)?;
+
+        if reused {
+            return Err(AppError::ValidationError(PasswordPolicy::reused_error()));
+        }
         Ok(())
     }
+
+    fn record_password_history(&self, conn: &mut PgConnection, user_id: i32, hash_val: &str) -> Result<(), AppError> {
+        if self.policy.history_size <= 0 {
+            return Ok(());
+        }
+
+        password_history_repository::insert(conn, user_id, hash_val)
+    }
 }
 
 #[async_trait]
 impl UserRepository for DieselUserRepository {
-    async fn create(&self, email: String, password: String, role: UserRole) -> Result<User, AppError> {
+    #[instrument(name = "UserRepository.create", skip_all, fields(db.system = "postgresql"))]
+    async fn create(&self, email: String, password_hash: String, role: UserRole) -> Result<User, AppError> {
         use crate::schema::users;
-        
-        // Validate email
-        let user = User {
+
+        let new_user = User {
             id: 0,
-            email: email.clone(),
-            password: String::new(),
+            email,
+            password: password_hash,
             role,
             is_email_verified: false,
             deleted_at: None,
             created_at: chrono::Utc::now().naive_utc(),
+            version: 1,
+            updated_at: chrono::Utc::now().naive_utc(),
+            name: None,
         };
-        user.validate().map_err(|_| AppError::InvalidEmail)?;
-
-        // Validate password
-        self.validate_password(&password)?;
-
-        let hashed_password = hash(password.as_bytes(), DEFAULT_COST)
-            .map_err(|_| AppError::InternalServerError)?;
-
-        let new_user = User {
-            password: hashed_password,
-            ..user
-        };
-
-        let conn = &mut self.pool.get()
-            .map_err(|_| AppError::DatabaseError(diesel::result::Error::NotFound))?;
-
-        diesel::insert_into(users::table)
-            .values(&new_user)
-            .get_result(conn)
-            .map_err(AppError::DatabaseError)
+        new_user.validate()?;
+
+        self.source.run(|conn| {
+            diesel::insert_into(users::table)
+                .values(&new_user)
+                .get_result(conn)
+                .map_err(AppError::from)
+        })
     }
 
-    async fn find_by_id(&self, id: i32) -> Result<Option<User>, AppError> {
+    #[instrument(name = "UserRepository.find_by_id", skip_all, fields(db.system = "postgresql", user.id = user_id))]
+    async fn find_by_id(&self, user_id: i32) -> Result<Option<User>, AppError> {
         use crate::schema::users::dsl::*;
 
-        let conn = &mut self.pool.get()
-            .map_err(|_| AppError::DatabaseError(diesel::result::Error::NotFound))?;
-
-        users.find(id)
-            .first(conn)
-            .optional()
-            .map_err(AppError::DatabaseError)
+        self.source.run_read(|conn| {
+            users.find(user_id)
+                .first(conn)
+                .optional()
+                .map_err(AppError::from)
+        })
     }
 
+    #[instrument(name = "UserRepository.find_by_email", skip_all, fields(db.system = "postgresql"))]
     async fn find_by_email(&self, email_query: &str) -> Result<Option<User>, AppError> {
         use crate::schema::users::dsl::*;
 
-        let conn = &mut self.pool.get()
-            .map_err(|_| AppError::DatabaseError(diesel::result::Error::NotFound))?;
-
-        users.filter(email.eq(email_query))
-            .first(conn)
-            .optional()
-            .map_err(AppError::DatabaseError)
+        self.source.run_read(|conn| {
+            users.filter(email.eq(email_query))
+                .first(conn)
+                .optional()
+                .map_err(AppError::from)
+        })
     }
 
-    async fn update(&self, user_id: i32, email_update: Option<String>, password_update: Option<String>) -> Result<User, AppError> {
+    #[instrument(name = "UserRepository.update", skip_all, fields(db.system = "postgresql", user.id = user_id))]
+    async fn update(
+        &self,
+        user_id: i32,
+        email_update: Option<String>,
+        password_update: Option<String>,
+        role_update: Option<UserRole>,
+        expected_version: Option<i32>,
+    ) -> Result<User, AppError> {
         use crate::schema::users::dsl::*;
 
-        let conn = &mut self.pool.get()
-            .map_err(|_| AppError::DatabaseError(diesel::result::Error::NotFound))?;
-
-        let mut update = diesel::update(users.find(user_id));
-
-        if let Some(email_val) = email_update {
-            update = update.set(email.eq(email_val));
-        }
+        let password_update = match password_update {
+            Some(password_val) => {
+                // Policy checks need the email the user will have after this update
+                let current = self.source.run(|conn| {
+                    users.find(user_id).first::<User>(conn).map_err(AppError::from)
+                })?;
+                let email_for_check = email_update.as_deref().unwrap_or(&current.email);
+                self.policy.validate(&password_val, Some(email_for_check))?;
+                self.check_password_history(user_id, &password_val).await?;
+
+                Some(hash_password(&password_val)
+                    .map_err(|_| AppError::InternalServerError)?)
+            }
+            None => None,
+        };
 
-        if let Some(password_val) = password_update {
-            let hashed_password = hash(password_val.as_bytes(), DEFAULT_COST)
-                .map_err(|_| AppError::InternalServerError)?;
-            update = update.set(password.eq(hashed_password));
-        }
+        let changes = UserChanges {
+            email: email_update,
+            password: password_update,
+            role: role_update,
+            updated_at: chrono::Utc::now().naive_utc(),
+        };
 
-        update
-            .get_result(conn)
-            .map_err(AppError::DatabaseError)
+        self.source.run(|conn| conn.transaction(|conn| {
+            let mut statement = diesel::update(users.filter(id.eq(user_id)))
+                .set((&changes, version.eq(version + 1)))
+                .into_boxed();
+            if let Some(expected) = expected_version {
+                statement = statement.filter(version.eq(expected));
+            }
+
+            let updated: Option<User> = statement
+                .get_result(conn)
+                .optional()
+                .map_err(AppError::from)?;
+
+            match updated {
+                Some(user) => {
+                    if let Some(hash_val) = &changes.password {
+                        self.record_password_history(conn, user.id, hash_val)?;
+                    }
+                    Ok(user)
+                }
+                None => {
+                    // Distinguish a stale version from a missing user
+                    let exists = diesel::select(diesel::dsl::exists(users.find(user_id)))
+                        .get_result::<bool>(conn)
+                        .map_err(AppError::from)?;
+
+                    if exists {
+                        Err(AppError::PreconditionFailed)
+                    } else {
+                        Err(AppError::NotFound)
+                    }
+                }
+            }
+        }))
     }
 
-    async fn delete(&self, user_id: i32) -> Result<bool, AppError> {
+    #[instrument(name = "UserRepository.upsert_by_email", skip_all, fields(db.system = "postgresql"))]
+    async fn upsert_by_email(
+        &self,
+        email_val: String,
+        password_hash: Option<String>,
+        role_val: Option<UserRole>,
+    ) -> Result<UpsertOutcome, AppError> {
         use crate::schema::users::dsl::*;
 
-        let conn = &mut self.pool.get()
-            .map_err(|_| AppError::DatabaseError(diesel::result::Error::NotFound))?;
-
-        let deleted = diesel::delete(users.find(user_id))
-            .execute(conn)
-            .map_err(AppError::DatabaseError)?;
-
-        Ok(deleted > 0)
+        self.source.run(|conn| conn.transaction(|conn| {
+            let now = chrono::Utc::now().naive_utc();
+
+            // Lock the current row, if any, so it cannot be soft-deleted
+            // while we update it; deleted accounts are never resurrected
+            let existing = users.filter(email.eq(&email_val))
+                .for_update()
+                .first::<User>(conn)
+                .optional()
+                .map_err(AppError::from)?;
+            if existing.as_ref().is_some_and(|user| user.deleted_at.is_some()) {
+                return Err(AppError::Conflict);
+            }
+
+            let changes = UserChanges {
+                email: None,
+                password: password_hash.clone(),
+                role: role_val,
+                updated_at: now,
+            };
+
+            let (user, created) = match (existing, &password_hash) {
+                (Some(user), _) => {
+                    let user: User = diesel::update(users.find(user.id))
+                        .set((&changes, version.eq(version + 1)))
+                        .get_result(conn)
+                        .map_err(AppError::from)?;
+                    (user, false)
+                }
+                (None, None) => return Err(AppError::InvalidPassword),
+                (None, Some(hash_val)) => {
+                    let new_user = User {
+                        id: 0,
+                        email: email_val,
+                        password: hash_val.clone(),
+                        role: role_val.unwrap_or(UserRole::User),
+                        is_email_verified: false,
+                        deleted_at: None,
+                        created_at: now,
+                        version: 1,
+                        updated_at: now,
+                        name: None,
+                    };
+
+                    // A concurrent insert of the same email turns into an
+                    // update; `xmax = 0` only holds for freshly inserted rows
+                    diesel::insert_into(users)
+                        .values(&new_user)
+                        .on_conflict(email)
+                        .do_update()
+                        .set((&changes, version.eq(version + 1)))
+                        .returning((crate::schema::users::all_columns, sql::<Bool>("xmax = 0")))
+                        .get_result::<(User, bool)>(conn)
+                        .map_err(AppError::from)?
+                }
+            };
+
+            if let Some(hash_val) = &password_hash {
+                self.record_password_history(conn, user.id, hash_val)?;
+            }
+
+            Ok(if created {
+                UpsertOutcome::Created
+            } else {
+                UpsertOutcome::Updated
+            })
+        }))
     }
 
+    #[instrument(name = "UserRepository.soft_delete", skip_all, fields(db.system = "postgresql", user.id = user_id))]
     async fn soft_delete(&self, user_id: i32) -> Result<bool, AppError> {
         use crate::schema::users::dsl::*;
 
-        let conn = &mut self.pool.get()
-            .map_err(|_| AppError::DatabaseError(diesel::result::Error::NotFound))?;
+        self.source.run(|conn| {
+            let now = chrono::Utc::now().naive_utc();
+            let updated = diesel::update(users.find(user_id))
+                .set((deleted_at.eq(now), version.eq(version + 1), updated_at.eq(now)))
+                .execute(conn)
+                .map_err(AppError::from)?;
 
-        let now = chrono::Utc::now().naive_utc();
-        let updated = diesel::update(users.find(user_id))
-            .set(deleted_at.eq(now))
-            .execute(conn)
-            .map_err(AppError::DatabaseError)?;
-
-        Ok(updated > 0)
+            Ok(updated > 0)
+        })
     }
 
-    async fn list(&self, limit_val: i64, offset_val: i64, include_deleted: bool) -> Result<Vec<User>, AppError> {
+    #[instrument(name = "UserRepository.list", skip_all, fields(db.system = "postgresql"))]
+    async fn list(&self, list_query: &UserListQuery) -> Result<Vec<User>, AppError> {
         use crate::schema::users::dsl::*;
 
-        let conn = &mut self.pool.get()
-            .map_err(|_| AppError::DatabaseError(diesel::result::Error::NotFound))?;
+        self.source.run_read(|conn| {
+            let filter = &list_query.filter;
+            let mut query = users.into_boxed();
+
+            if !filter.include_deleted {
+                query = query.filter(deleted_at.is_null());
+            }
+            if let Some(role_val) = filter.role {
+                query = query.filter(role.eq(role_val));
+            }
+            if let Some(verified) = filter.is_email_verified {
+                query = query.filter(is_email_verified.eq(verified));
+            }
+            if let Some(after) = filter.created_after {
+                query = query.filter(created_at.ge(after));
+            }
+            if let Some(before) = filter.created_before {
+                query = query.filter(created_at.lt(before));
+            }
+            if let Some(needle) = &filter.email_contains {
+                query = query.filter(email.ilike(format!("%{}%", escape_like(needle))));
+            }
+            if let Some(needle) = &filter.email_or_name_contains {
+                let pattern = format!("%{}%", escape_like(needle));
+                query = query.filter(email.ilike(pattern.clone()).or(name.ilike(pattern)));
+            }
+
+            // Keyset pagination: continue strictly after the cursor row
+            let ascending = list_query.direction == SortDirection::Asc;
+            query = match (&list_query.after, ascending) {
+                (Some(UserCursor::CreatedAt { created_at: c, id: last_id }), true) => query.filter(
+                    created_at.gt(*c).or(created_at.eq(*c).and(id.gt(*last_id))),
+                ),
+                (Some(UserCursor::CreatedAt { created_at: c, id: last_id }), false) => query.filter(
+                    created_at.lt(*c).or(created_at.eq(*c).and(id.lt(*last_id))),
+                ),
+                (Some(UserCursor::Email { email: e, id: last_id }), true) => query.filter(
+                    email.gt(e.clone()).or(email.eq(e.clone()).and(id.gt(*last_id))),
+                ),
+                (Some(UserCursor::Email { email: e, id: last_id }), false) => query.filter(
+                    email.lt(e.clone()).or(email.eq(e.clone()).and(id.lt(*last_id))),
+                ),
+                (None, _) => query,
+            };
+
+            query = match (list_query.sort, ascending) {
+                (UserSortField::CreatedAt, true) => query.order((created_at.asc(), id.asc())),
+                (UserSortField::CreatedAt, false) => query.order((created_at.desc(), id.desc())),
+                (UserSortField::Email, true) => query.order((email.asc(), id.asc())),
+                (UserSortField::Email, false) => query.order((email.desc(), id.desc())),
+            };
+
+            query
+                .limit(list_query.limit)
+                .load(conn)
+                .map_err(AppError::from)
+        })
+    }
 
-        let mut query = users.into_boxed();
-        
-        if !include_deleted {
-            query = query.filter(deleted_at.is_null());
-        }
+    #[instrument(name = "UserRepository.search", skip_all, fields(db.system = "postgresql"))]
+    async fn search(&self, term: &str, limit_val: i64) -> Result<Vec<UserSearchHit>, AppError> {
+        use crate::schema::users::dsl::*;
 
-        query
-            .limit(limit_val)
-            .offset(offset_val)
-            .load(conn)
-            .map_err(AppError::DatabaseError)
+        self.source.run_read(|conn| conn.transaction(|conn| {
+            // `%` reads its threshold from this setting; LOCAL keeps it from
+            // leaking to the next user of the pooled connection
+            diesel::sql_query(format!(
+                "SET LOCAL pg_trgm.similarity_threshold = {}",
+                SEARCH_SIMILARITY_THRESHOLD,
+            ))
+            .execute(conn)?;
+
+            let pattern = format!("%{}%", escape_like(term));
+            // Best of the email and profile name matches, for ranking only
+            let score = greatest(
+                similarity(email, term.to_string()),
+                similarity(coalesce(name, ""), term.to_string()),
+            );
+
+            let rows: Vec<(User, f32)> = users
+                .select((crate::schema::users::all_columns, score.clone()))
+                .filter(deleted_at.is_null())
+                .filter(
+                    email.ilike(pattern.clone())
+                        .or(name.ilike(pattern))
+                        .or(TrigramMatches::new(email, term.to_string().into_sql::<Text>()))
+                        .or(TrigramMatches::new(name, term.to_string().into_sql::<Text>())),
+                )
+                .order((score.desc(), id.asc()))
+                .limit(limit_val)
+                .load(conn)
+                .map_err(AppError::from)?;
+
+            Ok(rows
+                .into_iter()
+                .map(|(user, rank)| UserSearchHit::new(user, term, rank))
+                .collect())
+        }))
     }
 
+    #[instrument(name = "UserRepository.verify_email", skip_all, fields(db.system = "postgresql", user.id = user_id))]
     async fn verify_email(&self, user_id: i32) -> Result<User, AppError> {
         use crate::schema::users::dsl::*;
 
-        let conn = &mut self.pool.get()
-            .map_err(|_| AppError::DatabaseError(diesel::result::Error::NotFound))?;
-
-        diesel::update(users.find(user_id))
-            .set(is_email_verified.eq(true))
-            .get_result(conn)
-            .map_err(AppError::DatabaseError)
+        self.source.run(|conn| {
+            diesel::update(users.find(user_id))
+                .set((
+                    is_email_verified.eq(true),
+                    version.eq(version + 1),
+                    updated_at.eq(chrono::Utc::now().naive_utc()),
+                ))
+                .get_result(conn)
+                .map_err(AppError::from)
+        })
     }
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 } 
\ No newline at end of file
@@ -0,0 +1,29 @@
+use std::future::Future;
+use unic_langid::LanguageIdentifier;
+use crate::infrastructure::i18n;
+
+// Per-request values set by the HTTP middleware and read wherever a response
+// is built, e.g. `AppError::into_response`. Kept here rather than next to the
+// middleware so infrastructure never depends on the application layer.
+tokio::task_local! {
+    static REQUEST_ID: String;
+    static LOCALE: LanguageIdentifier;
+}
+
+// Id of the request currently being handled, if any
+pub fn request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+// Locale negotiated for the current request, or the default outside of one
+pub fn locale() -> LanguageIdentifier {
+    LOCALE.try_with(Clone::clone).unwrap_or_else(|_| i18n::default_locale())
+}
+
+pub async fn with_request_id<F: Future>(id: String, f: F) -> F::Output {
+    REQUEST_ID.scope(id, f).await
+}
+
+pub async fn with_locale<F: Future>(locale: LanguageIdentifier, f: F) -> F::Output {
+    LOCALE.scope(locale, f).await
+} 
\ No newline at end of file
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use zeroize::Zeroizing;
+use crate::infrastructure::secrets::{file::read_secret_file, SecretError, SecretProvider};
+
+const NONCE_LEN: usize = 12;
+
+// Local secrets file: base64(nonce || ChaCha20-Poly1305 ciphertext) of a JSON
+// object mapping names to values. Referenced as `enc:<name>`.
+pub struct EncryptedFileProvider {
+    secrets: HashMap<String, Zeroizing<String>>,
+}
+
+fn decode_key(encoded: &str) -> Result<Zeroizing<Vec<u8>>, SecretError> {
+    let key = Zeroizing::new(
+        STANDARD
+            .decode(encoded.trim())
+            .map_err(|_| SecretError::Decryption("SECRETS_KEY must be base64".to_string()))?,
+    );
+    if key.len() != 32 {
+        return Err(SecretError::Decryption("SECRETS_KEY must be 32 bytes".to_string()));
+    }
+    Ok(key)
+}
+
+fn key_from_env() -> Result<Zeroizing<Vec<u8>>, SecretError> {
+    let encoded = match (env::var("SECRETS_KEY"), env::var("SECRETS_KEY_FILE")) {
+        (Ok(key), _) => Zeroizing::new(key),
+        (Err(_), Ok(path)) => Zeroizing::new(read_secret_file(&path)?),
+        _ => {
+            return Err(SecretError::Decryption(
+                "SECRETS_FILE is set but neither SECRETS_KEY nor SECRETS_KEY_FILE is".to_string(),
+            ))
+        }
+    };
+    decode_key(&encoded)
+}
+
+pub fn generate_key() -> String {
+    STANDARD.encode(ChaCha20Poly1305::generate_key(&mut OsRng))
+}
+
+pub fn encrypt(key: &str, plaintext: &[u8]) -> Result<String, SecretError> {
+    let key = decode_key(key)?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| SecretError::Decryption("encryption failed".to_string()))?;
+
+    let mut out = nonce.to_vec();
+    out.extend(ciphertext);
+    Ok(STANDARD.encode(out))
+}
+
+fn decrypt(key: &[u8], encoded: &str) -> Result<Zeroizing<Vec<u8>>, SecretError> {
+    let data = STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| SecretError::Decryption("secrets file is not valid base64".to_string()))?;
+    if data.len() <= NONCE_LEN {
+        return Err(SecretError::Decryption("secrets file is truncated".to_string()));
+    }
+
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map(Zeroizing::new)
+        .map_err(|_| SecretError::Decryption("wrong key or corrupted secrets file".to_string()))
+}
+
+impl EncryptedFileProvider {
+    pub fn from_env() -> Result<Option<Self>, SecretError> {
+        let Ok(path) = env::var("SECRETS_FILE") else {
+            return Ok(None);
+        };
+
+        let encoded = fs::read_to_string(&path)
+            .map_err(|e| SecretError::Io(format!("failed to read {}: {}", path, e)))?;
+        let plaintext = decrypt(&key_from_env()?, &encoded)?;
+        let secrets: HashMap<String, String> = serde_json::from_slice(&plaintext)
+            .map_err(|_| SecretError::Decryption("secrets file must hold a JSON object of strings".to_string()))?;
+
+        Ok(Some(Self {
+            secrets: secrets.into_iter().map(|(k, v)| (k, Zeroizing::new(v))).collect(),
+        }))
+    }
+}
+
+impl SecretProvider for EncryptedFileProvider {
+    fn scheme(&self) -> &'static str {
+        "enc"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<String, SecretError> {
+        self.secrets
+            .get(reference)
+            .map(|value| value.to_string())
+            .ok_or_else(|| SecretError::NotFound(format!("enc:{}", reference)))
+    }
+} 
\ No newline at end of file
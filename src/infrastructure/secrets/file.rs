@@ -0,0 +1,27 @@
+use std::fs;
+use crate::infrastructure::secrets::{SecretError, SecretProvider};
+
+// Reads a secret from a file, as mounted by Docker or Kubernetes secrets.
+// A single trailing newline is dropped.
+pub struct FileProvider;
+
+pub fn read_secret_file(path: &str) -> Result<String, SecretError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| SecretError::Io(format!("failed to read {}: {}", path, e)))?;
+
+    Ok(contents
+        .strip_suffix('\n')
+        .map(|c| c.strip_suffix('\r').unwrap_or(c))
+        .unwrap_or(&contents)
+        .to_string())
+}
+
+impl SecretProvider for FileProvider {
+    fn scheme(&self) -> &'static str {
+        "file"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<String, SecretError> {
+        read_secret_file(reference)
+    }
+} 
\ No newline at end of file
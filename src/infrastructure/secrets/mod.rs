@@ -0,0 +1,75 @@
+pub mod encrypted;
+pub mod file;
+pub mod secret;
+pub mod vault;
+
+use thiserror::Error;
+
+const SCHEMES: &[&str] = &["file", "enc", "vault"];
+
+#[derive(Error, Debug)]
+pub enum SecretError {
+    #[error("{0}")]
+    Io(String),
+    #[error("{0}")]
+    Decryption(String),
+    #[error("{0}")]
+    Remote(String),
+    #[error("secret `{0}` not found")]
+    NotFound(String),
+    #[error("no secret provider configured for `{0}:` references")]
+    NoProvider(String),
+}
+
+// Resolves the part of a secret reference after its scheme, e.g. the path
+// in `file:/run/secrets/jwt`.
+pub trait SecretProvider: Send + Sync {
+    fn scheme(&self) -> &'static str;
+    fn resolve(&self, reference: &str) -> Result<String, SecretError>;
+}
+
+pub struct SecretResolver {
+    providers: Vec<Box<dyn SecretProvider>>,
+}
+
+impl SecretResolver {
+    // Providers available from the environment: files always, the encrypted
+    // secrets file when `SECRETS_FILE` is set and Vault when `VAULT_ADDR` is.
+    pub fn from_env() -> Result<Self, SecretError> {
+        let mut providers: Vec<Box<dyn SecretProvider>> = vec![Box::new(file::FileProvider)];
+
+        if let Some(provider) = encrypted::EncryptedFileProvider::from_env()? {
+            providers.push(Box::new(provider));
+        }
+        if let Some(provider) = vault::VaultProvider::from_env()? {
+            providers.push(Box::new(provider));
+        }
+
+        Ok(Self { providers })
+    }
+
+    pub fn is_reference(value: &str) -> bool {
+        value
+            .split_once(':')
+            .map(|(scheme, _)| SCHEMES.contains(&scheme))
+            .unwrap_or(false)
+    }
+
+    // Values with a known `scheme:` prefix are looked up; anything else is
+    // returned unchanged so plain values keep working.
+    pub fn resolve(&self, value: &str) -> Result<String, SecretError> {
+        let Some((scheme, reference)) = value.split_once(':') else {
+            return Ok(value.to_string());
+        };
+
+        if !SCHEMES.contains(&scheme) {
+            return Ok(value.to_string());
+        }
+
+        self.providers
+            .iter()
+            .find(|provider| provider.scheme() == scheme)
+            .ok_or_else(|| SecretError::NoProvider(scheme.to_string()))?
+            .resolve(reference)
+    }
+} 
\ No newline at end of file
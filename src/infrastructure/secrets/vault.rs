@@ -0,0 +1,60 @@
+use std::env;
+use std::time::Duration;
+use zeroize::Zeroizing;
+use crate::infrastructure::secrets::{file::read_secret_file, SecretError, SecretProvider};
+
+// Reads from a Vault-compatible KV v2 HTTP API (Vault itself or a local
+// stand-in). References look like `vault:secret/data/app#jwt_secret`.
+pub struct VaultProvider {
+    addr: String,
+    token: Zeroizing<String>,
+    agent: ureq::Agent,
+}
+
+impl VaultProvider {
+    pub fn from_env() -> Result<Option<Self>, SecretError> {
+        let Ok(addr) = env::var("VAULT_ADDR") else {
+            return Ok(None);
+        };
+
+        let token = match (env::var("VAULT_TOKEN"), env::var("VAULT_TOKEN_FILE")) {
+            (Ok(token), _) => token,
+            (Err(_), Ok(path)) => read_secret_file(&path)?,
+            _ => return Err(SecretError::Remote("VAULT_ADDR is set but VAULT_TOKEN is not".to_string())),
+        };
+
+        Ok(Some(Self {
+            addr: addr.trim_end_matches('/').to_string(),
+            token: Zeroizing::new(token),
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(5)).build(),
+        }))
+    }
+}
+
+impl SecretProvider for VaultProvider {
+    fn scheme(&self) -> &'static str {
+        "vault"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<String, SecretError> {
+        let (path, key) = reference
+            .split_once('#')
+            .ok_or_else(|| SecretError::Remote(format!("vault:{} must be <path>#<key>", reference)))?;
+
+        let body: serde_json::Value = self.agent
+            .get(&format!("{}/v1/{}", self.addr, path.trim_start_matches('/')))
+            .set("X-Vault-Token", &self.token)
+            .call()
+            .map_err(|e| SecretError::Remote(format!("vault request for {} failed: {}", path, e)))?
+            .into_json()
+            .map_err(|e| SecretError::Remote(format!("invalid vault response for {}: {}", path, e)))?;
+
+        // KV v2 nests values under data.data; KV v1 directly under data
+        body.pointer("/data/data")
+            .or_else(|| body.get("data"))
+            .and_then(|data| data.get(key))
+            .and_then(|value| value.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| SecretError::NotFound(format!("vault:{}", reference)))
+    }
+} 
\ No newline at end of file
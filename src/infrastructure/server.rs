@@ -3,123 +3,514 @@ use axum::{
     routing::{get, post},
     Router,
     middleware,
-    http::{Method, HeaderValue},
-    Json,
+    body::Body,
+    extract::{DefaultBodyLimit, State},
+    http::{
+        header::{self, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, SET_COOKIE},
+        HeaderName, HeaderValue, Method, Request,
+    },
+    response::IntoResponse,
 };
 use tower_http::{
-    cors::{CorsLayer, Any},
+    cors::{AllowHeaders, AllowOrigin, CorsLayer},
+    sensitive_headers::{SetSensitiveRequestHeadersLayer, SetSensitiveResponseHeadersLayer},
+    classify::{ServerErrorsAsFailures, SharedClassifier},
     trace::TraceLayer,
-    limit::RateLimitLayer,
 };
+use utoipa_swagger_ui::SwaggerUi;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use hyper_util::{rt::TokioExecutor, server::conn::auto};
+use opentelemetry::global;
 use tracing::Level;
-use tower::limit::RateLimit;
-use std::time::Duration;
-use serde_json::json;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, reload, Layer, util::SubscriberInitExt, EnvFilter};
+use std::time::{Duration, Instant};
+use std::sync::Arc;
+use arc_swap::ArcSwap;
 use crate::{
-    infrastructure::config::{database::DbPool, app::AppConfig},
+    infrastructure::config::{
+        database::DbPool,
+        app::{AppConfig, IdempotencyBackend, LogFormat, RateLimitBackend},
+        reload::{Reloader, RuntimeSettings, SharedSettings},
+    },
+    infrastructure::shutdown::{termination_signal, Shutdown},
+    infrastructure::tls::{self, TlsAcceptor},
+    infrastructure::health::HealthChecker,
+    infrastructure::metrics,
+    infrastructure::idempotency::{self, IdempotencyStore},
+    infrastructure::mail::Mailer,
+    infrastructure::rate_limit::{MemoryStore, PostgresStore, RateLimitStore},
+    infrastructure::request_context,
+    infrastructure::telemetry::{self, HeaderExtractor},
     infrastructure::auth::jwt::JwtService,
-    application::{handlers, middleware::auth::auth_middleware},
-    domain::repositories::user_repository::DieselUserRepository,
-    application::handlers::users,
+    infrastructure::db::replica::DbRouter,
+    application::{
+        handlers::{self, auth::EmailVerification, v2},
+        openapi,
+        state::AppState,
+        middleware::{
+            api_version::{version_lifecycle, VersionLifecycle},
+            auth::{auth_middleware, optional_auth, require_admin},
+            idempotency::{idempotency, Idempotency},
+            locale::locale,
+            metrics::{record_matched_route, track_metrics},
+            rate_limit::{rate_limit, RateLimiter},
+            read_your_writes::read_your_writes,
+            request_id::request_id,
+            security::{harden, Hardening},
+            trace_context::trace_context,
+        },
+    },
+    infrastructure::db::transaction::DieselUnitOfWork,
+    domain::services::password::PasswordService,
+    infrastructure::repositories::user_repository::DieselUserRepository,
+    application::handlers::{user_transfer, users},
 };
 
+// Idle buckets older than this are dropped; every policy window is shorter
+const RATE_LIMIT_PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+const IDEMPOTENCY_PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+type RequestTraceLayer = TraceLayer<
+    SharedClassifier<ServerErrorsAsFailures>,
+    fn(&Request<Body>) -> tracing::Span,
+    fn(&Request<Body>, &tracing::Span),
+>;
+
+// Fields on this span are attached to every log line written while handling
+// the request
+fn request_span(request: &Request<Body>) -> tracing::Span {
+    let span = tracing::span!(
+        Level::INFO,
+        "request",
+        request_id = request_context::request_id().unwrap_or_default(),
+        method = %request.method(),
+        uri = %request.uri(),
+        otel.kind = "server",
+        enduser.id = tracing::field::Empty,
+        tls.client.subject = tracing::field::Empty,
+        tls.client.serial = tracing::field::Empty,
+    );
+    // Continue the caller's trace when it sent a `traceparent`
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    span.set_parent(parent);
+    span
+}
+
+fn log_request_started(request: &Request<Body>, _span: &tracing::Span) {
+    tracing::debug!(headers = ?request.headers(), "request started");
+}
+
 pub struct Server {
     config: AppConfig,
     db_pool: DbPool,
+    db_router: Arc<DbRouter>,
     jwt_service: JwtService,
+    passwords: PasswordService,
+    mailer: Mailer,
+    settings: SharedSettings,
+    rate_limit_store: Arc<dyn RateLimitStore>,
+    idempotency_store: Arc<dyn IdempotencyStore>,
 }
 
 impl Server {
-    pub fn new(config: AppConfig, db_pool: DbPool, jwt_service: JwtService) -> Self {
+    pub fn new(
+        config: AppConfig,
+        db_pool: DbPool,
+        db_router: Arc<DbRouter>,
+        jwt_service: JwtService,
+        passwords: PasswordService,
+        mailer: Mailer,
+    ) -> Self {
+        let settings = Arc::new(ArcSwap::from_pointee(RuntimeSettings::from(&config)));
+        let rate_limit_store: Arc<dyn RateLimitStore> = match config.rate_limit.backend {
+            RateLimitBackend::Memory => Arc::new(MemoryStore::default()),
+            RateLimitBackend::Postgres => Arc::new(PostgresStore::new(db_pool.clone())),
+        };
+        let idempotency_store: Arc<dyn IdempotencyStore> = match config.idempotency.backend {
+            IdempotencyBackend::Memory => Arc::new(idempotency::MemoryStore::default()),
+            IdempotencyBackend::Postgres => Arc::new(idempotency::PostgresStore::new(db_pool.clone())),
+        };
+
         Self {
             config,
             db_pool,
+            db_router,
             jwt_service,
+            passwords,
+            mailer,
+            settings,
+            rate_limit_store,
+            idempotency_store,
         }
     }
 
     fn setup_cors(&self) -> CorsLayer {
+        let cors = &self.config.cors;
+
+        // Checked per request against the live settings so reloads apply
+        let settings = self.settings.clone();
+        let allow_origin = AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+            origin
+                .to_str()
+                .map(|origin| settings.load().cors.allows_origin(origin))
+                .unwrap_or(false)
+        });
+
+        let allow_headers = if cors.allowed_headers.iter().any(|h| h == "*") {
+            AllowHeaders::any()
+        } else {
+            AllowHeaders::list(cors.allowed_headers.iter().filter_map(|h| h.parse().ok()))
+        };
+
+        // Validated at load time, so nothing is silently dropped here
         CorsLayer::new()
-            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-            .allow_headers(Any)
-            .allow_origin(Any)
+            .allow_origin(allow_origin)
+            .allow_methods(cors.allowed_methods.iter().filter_map(|m| m.parse::<Method>().ok()).collect::<Vec<_>>())
+            .allow_headers(allow_headers)
+            .expose_headers(cors.exposed_headers.iter().filter_map(|h| h.parse::<HeaderName>().ok()).collect::<Vec<_>>())
+            .allow_credentials(cors.allow_credentials)
+            .max_age(Duration::from_secs(cors.max_age_secs))
+            // Responses differ by these request headers; tell caches
+            .vary([header::ORIGIN, header::ACCESS_CONTROL_REQUEST_METHOD, header::ACCESS_CONTROL_REQUEST_HEADERS])
     }
 
-    fn setup_logging(&self) -> TraceLayer {
+    fn setup_logging(&self) -> RequestTraceLayer {
         TraceLayer::new_for_http()
-            .make_span_with(|request: &axum::http::Request<_>| {
-                tracing::span!(
-                    Level::INFO,
-                    "request",
-                    method = %request.method(),
-                    uri = %request.uri(),
-                )
-            })
+            .make_span_with(request_span as fn(&Request<Body>) -> tracing::Span)
+            // Headers marked sensitive below are printed as `Sensitive`
+            .on_request(log_request_started as fn(&Request<Body>, &tracing::Span))
     }
 
-    async fn health_check() -> Json<serde_json::Value> {
-        Json(json!({
-            "status": "healthy",
-            "timestamp": chrono::Utc::now().to_rfc3339()
-        }))
+    async fn metrics(State(router): State<Arc<DbRouter>>) -> impl IntoResponse {
+        (
+            [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+            metrics::render(&router),
+        )
+    }
+
+    fn create_metrics_router(&self) -> Router {
+        Router::new()
+            .route("/metrics", get(Self::metrics))
+            .with_state(self.db_router.clone())
+            .merge(
+                Router::new()
+                    .route("/health/ready", get(handlers::health::readiness_details))
+                    .with_state(HealthChecker::new(self.db_router.clone(), &self.config.mail)),
+            )
     }
 
     fn create_router(&self) -> Router {
-        // Rate limit configuration from env
-        let rate_limit = RateLimit::new(
-            self.config.rate_limit_requests,
-            Duration::from_secs(self.config.rate_limit_duration),
-            RateLimitLayer::new(),
+        // Rate limits are read from the reloadable settings
+        let rate_limiter = RateLimiter::new(
+            self.settings.clone(),
+            self.rate_limit_store.clone(),
+            self.jwt_service.clone(),
         );
 
-        let user_repository = DieselUserRepository::new(self.db_pool.clone());
+        let idempotent_requests = Idempotency::new(
+            self.idempotency_store.clone(),
+            self.jwt_service.clone(),
+            &self.config.idempotency,
+            self.config.security.largest_body_limit(),
+        );
+
+        let password_policy = self.passwords.policy().clone();
+        let state = AppState {
+            users: DieselUserRepository::with_router(self.db_router.clone())
+                .with_policy(password_policy.clone()),
+            unit_of_work: DieselUnitOfWork::new(self.db_pool.clone())
+                .with_policy(password_policy),
+            passwords: self.passwords.clone(),
+            jwt_service: self.jwt_service.clone(),
+            email_verification: EmailVerification {
+                mailer: self.mailer.clone(),
+                required: self.config.auth.require_verified_email,
+            },
+        };
 
-        // Public routes
-        let public_routes = Router::new()
+        // Routes every API version serves the same way
+        let auth_routes = Router::new()
             .route("/auth/login", post(handlers::auth::login::<DieselUserRepository>))
-            .route("/auth/register", post(handlers::auth::register::<DieselUserRepository>))
-            .route("/health", get(Self::health_check))
-            .route("/users", post(users::create_user::<DieselUserRepository>))
-            .route("/users", get(users::list_users::<DieselUserRepository>));
-        
-        // Protected routes
+            .route("/auth/register", post(handlers::auth::register::<DieselUnitOfWork>))
+            .route("/auth/verify-email", post(handlers::auth::verify_email::<DieselUserRepository>))
+            .route("/auth/resend-verification", post(handlers::auth::resend_verification::<DieselUserRepository>));
+
         let protected_routes = Router::new()
             .route("/protected", get(handlers::protected::handler))
-            .route(
-                "/users/:id",
-                get(users::get_user::<DieselUserRepository>)
-                    .put(users::update_user::<DieselUserRepository>)
-                    .delete(users::delete_user::<DieselUserRepository>),
-            )
             .layer(middleware::from_fn_with_state(
                 self.jwt_service.clone(),
                 auth_middleware,
             ));
-        
+
+        // Admin routes
+        let admin_routes = Router::new()
+            .route("/admin/users/import", post(user_transfer::import_users::<DieselUserRepository>))
+            .route("/admin/users/export", get(user_transfer::export_users::<DieselUserRepository>))
+            .layer(middleware::from_fn(require_admin))
+            .layer(middleware::from_fn_with_state(
+                self.jwt_service.clone(),
+                auth_middleware,
+            ));
+
+        // User routes differ per version only in their DTOs
+        let v1_routes = Router::new()
+            .route(
+                "/users",
+                post(users::create_user::<DieselUnitOfWork>)
+                    .get(users::list_users::<DieselUserRepository>),
+            )
+            .layer(middleware::from_fn_with_state(
+                self.jwt_service.clone(),
+                optional_auth,
+            ))
+            .merge(
+                Router::new()
+                    .route("/users/search", get(users::search_users::<DieselUserRepository>))
+                    .route(
+                        "/users/{id}",
+                        get(users::get_user::<DieselUserRepository>)
+                            .put(users::update_user::<DieselUserRepository>)
+                            .delete(users::delete_user::<DieselUserRepository>),
+                    )
+                    .layer(middleware::from_fn_with_state(
+                        self.jwt_service.clone(),
+                        auth_middleware,
+                    )),
+            );
+
+        // Listing v2 users requires a token; creating them doesn't
+        let v2_routes = Router::new()
+            .route("/users", post(v2::users::create_user::<DieselUnitOfWork>))
+            .merge(
+                Router::new()
+                    .route("/users", get(v2::users::list_users::<DieselUserRepository>))
+                    .route("/users/search", get(v2::users::search_users::<DieselUserRepository>))
+                    .route(
+                        "/users/{id}",
+                        get(v2::users::get_user::<DieselUserRepository>)
+                            .put(v2::users::update_user::<DieselUserRepository>)
+                            .delete(users::delete_user::<DieselUserRepository>),
+                    )
+                    .layer(middleware::from_fn_with_state(
+                        self.jwt_service.clone(),
+                        auth_middleware,
+                    )),
+            );
+
+        // Adds the shared routes and the lifecycle headers of a version
+        let versioned = |lifecycle: VersionLifecycle, routes: Router<AppState>| {
+            routes
+                .merge(auth_routes.clone())
+                .merge(protected_routes.clone())
+                .merge(admin_routes.clone())
+                .layer(middleware::from_fn_with_state(lifecycle, version_lifecycle))
+        };
+
+        // Probes; `/health` is kept as an alias for liveness
+        let health_routes = Router::new()
+            .route("/health", get(handlers::health::live))
+            .route("/health/live", get(handlers::health::live))
+            .route("/health/ready", get(handlers::health::ready))
+            .with_state(HealthChecker::new(self.db_router.clone(), &self.config.mail));
+
         // Combine all routes with middleware
         Router::new()
-            .merge(public_routes)
-            .merge(protected_routes)
-            .layer(self.setup_cors())
+            .nest("/api/v1", versioned(VersionLifecycle::new(&self.config.api, "v1"), v1_routes.clone()))
+            .nest("/api/v2", versioned(VersionLifecycle::new(&self.config.api, "v2"), v2_routes))
+            // The paths from before versioning, kept as deprecated v1 aliases
+            .merge(versioned(VersionLifecycle::unversioned(&self.config.api), v1_routes))
+            .merge(health_routes)
+            .merge(SwaggerUi::new("/docs").url("/openapi.json", openapi::spec()))
+            // Route layer, so the matched route template is known
+            .route_layer(middleware::from_fn(record_matched_route))
+            .layer(middleware::from_fn(read_your_writes))
+            .layer(middleware::from_fn_with_state(idempotent_requests, idempotency))
+            .layer(middleware::from_fn(trace_context))
             .layer(self.setup_logging())
-            .layer(rate_limit)
-            .with_state(self.db_pool.clone())
-            .with_state(self.jwt_service.clone())
+            .layer(SetSensitiveRequestHeadersLayer::new([AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE]))
+            .layer(SetSensitiveResponseHeadersLayer::new([SET_COOKIE]))
+            .layer(middleware::from_fn_with_state(rate_limiter, rate_limit))
+            .layer(middleware::from_fn_with_state(Hardening::new(&self.config.security), harden))
+            // Backstop for extractors; per-route limits are enforced by
+            // `harden`, for streamed bodies too
+            .layer(DefaultBodyLimit::max(self.config.security.largest_body_limit() as usize))
+            // Everything below wraps the rejections from rate limiting and
+            // hardening too: they are localized, carry CORS headers (and
+            // preflights are never limited), are counted, and echo the
+            // request id, which is set outermost
+            .layer(middleware::from_fn(locale))
+            .layer(self.setup_cors())
+            .layer(middleware::from_fn(track_metrics))
+            .layer(middleware::from_fn(request_id))
+            .with_state(state)
     }
 
-    pub async fn run(&self) {
-        // Setup tracing
-        tracing_subscriber::fmt()
-            .with_target(false)
-            .compact()
+    // Serves until SIGTERM/SIGINT, then stops accepting connections and gives
+    // in-flight requests and background tasks `shutdown_timeout_secs` to
+    // finish before the database pools are closed.
+    pub async fn run(self) {
+        // Setup tracing; the filter sits behind a reload handle so the log
+        // level can change with the config
+        let (filter, log_handle) = reload::Layer::new(EnvFilter::new(&self.config.logging.level));
+        let (tracer, tracer_error) = match telemetry::init_tracer(&self.config.telemetry) {
+            Ok(tracer) => (tracer, None),
+            Err(e) => (None, Some(e)),
+        };
+        let otel = tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer));
+        let fmt = match self.config.logging.format {
+            LogFormat::Text => tracing_subscriber::fmt::layer().with_target(false).compact().boxed(),
+            // The current span carries the request id, method and URI
+            LogFormat::Json => tracing_subscriber::fmt::layer()
+                .json()
+                .flatten_event(true)
+                .with_current_span(true)
+                .with_span_list(false)
+                .boxed(),
+        };
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt)
+            .with(otel)
             .init();
 
-        let addr = SocketAddr::from(([127, 0, 0, 1], self.config.port));
-        tracing::info!("Server running on http://{}", addr);
+        if let Some(e) = tracer_error {
+            tracing::warn!("Failed to set up OTLP exporter, traces will not be exported: {}", e);
+        }
+
+        let shutdown = Shutdown::new();
+
+        Reloader::new(
+            self.config.clone(),
+            self.settings.clone(),
+            self.jwt_service.clone(),
+            log_handle,
+        )
+        .spawn(shutdown.handle());
+
+        self.db_router.spawn_health_checks(
+            Duration::from_secs(self.config.database.replica_health_check_interval),
+            shutdown.handle(),
+        );
+
+        let server_config = &self.config.server;
+        let addr = SocketAddr::new(server_config.host, server_config.port);
+        let deadline = Duration::from_secs(server_config.shutdown_timeout_secs);
+
+        // Hyper stops accepting new connections once the signal fires and
+        // waits for in-flight ones up to the deadline
+        let handle = Handle::new();
+        let signal = handle.clone();
+        let signal_task = tokio::spawn(async move {
+            termination_signal().await;
+            tracing::info!("Draining in-flight requests (up to {}s)", deadline.as_secs());
+            signal.graceful_shutdown(Some(deadline));
+            Instant::now()
+        });
+
+        if self.config.metrics.enabled {
+            let metrics_addr = SocketAddr::new(self.config.metrics.host, self.config.metrics.port);
+            let metrics_server = axum_server::bind(metrics_addr)
+                .handle(handle.clone())
+                .serve(self.create_metrics_router().into_make_service());
+            tracing::info!("Metrics available on http://{}/metrics", metrics_addr);
+            tokio::spawn(async move {
+                if let Err(e) = metrics_server.await {
+                    tracing::error!("Metrics server error: {}", e);
+                }
+            });
+        }
+
+        // Rate limiting keys anonymous clients by peer address
+        let app = self.create_router().into_make_service_with_connect_info::<SocketAddr>();
+
+        let store = self.rate_limit_store.clone();
+        let signal = shutdown.handle();
+        shutdown.handle().spawn(async move {
+            let mut ticker = tokio::time::interval(RATE_LIMIT_PRUNE_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = signal.signalled() => return,
+                }
+                let store = store.clone();
+                if let Ok(Err(e)) = tokio::task::spawn_blocking(move || store.prune(RATE_LIMIT_PRUNE_INTERVAL)).await {
+                    tracing::warn!("Failed to prune rate limit buckets: {}", e);
+                }
+            }
+        });
+
+        let store = self.idempotency_store.clone();
+        let signal = shutdown.handle();
+        shutdown.handle().spawn(async move {
+            let mut ticker = tokio::time::interval(IDEMPOTENCY_PRUNE_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = signal.signalled() => return,
+                }
+                let store = store.clone();
+                if let Ok(Err(e)) = tokio::task::spawn_blocking(move || store.prune()).await {
+                    tracing::warn!("Failed to prune idempotency keys: {}", e);
+                }
+            }
+        });
+        // A TLS load error falls through to the shutdown below, so the
+        // background tasks started above are still drained
+        let result = match &server_config.tls {
+            Some(tls) => match tls::server_config(tls, server_config.http2) {
+                Ok(config) => {
+                    let rustls = RustlsConfig::from_config(Arc::new(config));
+                    tls::spawn_reloader(tls.clone(), server_config.http2, rustls.clone(), shutdown.handle());
+
+                    tracing::info!("Server running on https://{}", addr);
+                    let mut server = axum_server::bind(addr)
+                        .acceptor(TlsAcceptor::new(rustls))
+                        .handle(handle);
+                    *server.http_builder() = http_builder(server_config.http2);
+                    server.serve(app).await
+                }
+                Err(e) => Err(std::io::Error::new(e.kind(), format!("failed to load TLS certificate: {}", e))),
+            },
+            None => {
+                tracing::info!("Server running on http://{}", addr);
+                let mut server = axum_server::bind(addr).handle(handle);
+                *server.http_builder() = http_builder(server_config.http2);
+                server.serve(app).await
+            }
+        };
+        match result {
+            Ok(()) => tracing::info!("HTTP server stopped"),
+            Err(e) => tracing::error!("Server error: {}", e),
+        }
+
+        // Background tasks get whatever is left of the deadline
+        let started = if signal_task.is_finished() {
+            signal_task.await.unwrap_or_else(|_| Instant::now())
+        } else {
+            signal_task.abort();
+            Instant::now()
+        };
+        if !shutdown.drain(deadline.saturating_sub(started.elapsed())).await {
+            tracing::warn!("Background tasks still running at the shutdown deadline");
+        }
+
+        // The last pool handles go with the server; r2d2 closes every
+        // connection as the pools drop
+        drop(self);
+        tracing::info!("Database pools closed, shutdown complete");
+        telemetry::shutdown();
+    }
+}
 
-        axum::Server::bind(&addr)
-            .serve(self.create_router().into_make_service())
-            .await
-            .unwrap();
+// Serves HTTP/1 and, unless disabled, HTTP/2
+fn http_builder(http2: bool) -> auto::Builder<TokioExecutor> {
+    let builder = auto::Builder::new(TokioExecutor::new());
+    if http2 {
+        builder
+    } else {
+        builder.http1_only()
     }
 } 
\ No newline at end of file
@@ -0,0 +1,102 @@
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+// Coordinates process shutdown: a signal every background task can watch,
+// plus tracking so `drain` can wait for tracked tasks to finish their work.
+pub struct Shutdown {
+    trigger: watch::Sender<bool>,
+    handle: Option<ShutdownHandle>,
+    // Yields `None` once every tracked task (and handle) has been dropped
+    drained: mpsc::Receiver<()>,
+}
+
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    signal: watch::Receiver<bool>,
+    // Never sent on; each clone keeps `drained` open until dropped
+    _tracker: mpsc::Sender<()>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (trigger, signal) = watch::channel(false);
+        let (tracker, drained) = mpsc::channel(1);
+
+        Self {
+            trigger,
+            handle: Some(ShutdownHandle { signal, _tracker: tracker }),
+            drained,
+        }
+    }
+
+    pub fn handle(&self) -> ShutdownHandle {
+        self.handle.clone().expect("handle requested after drain")
+    }
+
+    pub fn trigger(&self) {
+        let _ = self.trigger.send(true);
+    }
+
+    // Signals shutdown and waits up to `deadline` for tracked tasks. Returns
+    // false if some were still running when the deadline passed.
+    pub async fn drain(mut self, deadline: Duration) -> bool {
+        self.trigger();
+        self.handle.take();
+        tokio::time::timeout(deadline, self.drained.recv()).await.is_ok()
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownHandle {
+    // Resolves once shutdown has been triggered
+    pub async fn signalled(&self) {
+        let mut signal = self.signal.clone();
+        let _ = signal.wait_for(|triggered| *triggered).await;
+    }
+
+    // Spawns a task that `Shutdown::drain` waits for. Long-running tasks
+    // should watch `signalled` and flush their work before returning.
+    pub fn spawn<F>(&self, task: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            let output = task.await;
+            drop(tracker);
+            output
+        })
+    }
+}
+
+// Resolves on SIGTERM (sent by Docker and Kubernetes) or Ctrl-C
+pub async fn termination_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("SIGINT received, shutting down"),
+        _ = terminate => tracing::info!("SIGTERM received, shutting down"),
+    }
+} 
\ No newline at end of file
@@ -0,0 +1,71 @@
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector},
+    trace::TraceError,
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{self, Sampler, Tracer},
+    Resource,
+};
+use crate::infrastructure::config::app::TelemetryConfig;
+
+// Installs the W3C trace context propagator and, when an OTLP endpoint is
+// configured, a batch exporter. Returns the tracer for the tracing layer;
+// this runs before the subscriber exists, so errors are left to the caller
+// to log once it does.
+pub fn init_tracer(config: &TelemetryConfig) -> Result<Option<Tracer>, TraceError> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let Some(endpoint) = config.otlp_endpoint.as_ref() else {
+        return Ok(None);
+    };
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(
+            trace::config()
+                // Respect the caller's sampling decision when there is one
+                .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio))))
+                .with_resource(Resource::new(vec![KeyValue::new("service.name", config.service_name.clone())])),
+        )
+        .install_batch(runtime::Tokio)
+        .map(Some)
+}
+
+// Flushes spans still buffered in the batch exporter
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
+
+// Trace context carriers over axum's headers; `opentelemetry-http` is built
+// on an older `http` crate
+pub struct HeaderExtractor<'a>(pub &'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+pub struct HeaderInjector<'a>(pub &'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(key.as_bytes()), HeaderValue::from_str(&value)) {
+            self.0.insert(name, value);
+        }
+    }
+} 
\ No newline at end of file
@@ -0,0 +1,208 @@
+use std::fs::File;
+use std::future::Future;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts},
+    http::request::Parts,
+};
+use axum_server::{
+    accept::{Accept, DefaultAcceptor},
+    tls_rustls::{RustlsAcceptor, RustlsConfig},
+};
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::server::TlsStream;
+use tower_http::add_extension::AddExtension;
+use crate::infrastructure::{
+    config::app::{ClientAuth, TlsConfig},
+    error::AppError,
+    shutdown::ShutdownHandle,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+// Identity taken from a verified client certificate
+#[derive(Debug, Clone)]
+pub struct ClientPrincipal {
+    pub common_name: String,
+    pub serial: String,
+}
+
+impl ClientPrincipal {
+    fn from_der(der: &[u8]) -> Option<Self> {
+        let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+        let common_name = cert.subject().iter_common_name().next()?.as_str().ok()?.to_string();
+
+        Some(Self {
+            common_name,
+            serial: cert.raw_serial_as_string(),
+        })
+    }
+}
+
+// Added to every request on a TLS connection
+#[derive(Debug, Clone)]
+pub struct TlsPeer {
+    pub principal: Option<ClientPrincipal>,
+}
+
+impl ClientPrincipal {
+    fn from_parts(parts: &Parts) -> Option<Self> {
+        parts
+            .extensions
+            .get::<TlsPeer>()
+            .and_then(|peer| peer.principal.clone())
+    }
+}
+
+// Extracts the client certificate principal, rejecting requests that didn't
+// present one
+impl<S: Send + Sync> FromRequestParts<S> for ClientPrincipal {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::from_parts(parts).ok_or(AppError::AuthenticationError)
+    }
+}
+
+// `Option<ClientPrincipal>`: `None` without mutual TLS or a certificate
+impl<S: Send + Sync> OptionalFromRequestParts<S> for ClientPrincipal {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Option<Self>, Self::Rejection> {
+        Ok(Self::from_parts(parts))
+    }
+}
+
+fn read_certs(path: &str) -> io::Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    rustls_pemfile::certs(&mut reader).collect()
+}
+
+fn read_key(path: &str) -> io::Result<PrivateKeyDer<'static>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("no private key in {}", path)))
+}
+
+fn invalid(e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+pub fn server_config(config: &TlsConfig, http2: bool) -> io::Result<ServerConfig> {
+    let verifier = match (&config.client_auth, &config.client_ca_file) {
+        (ClientAuth::None, _) | (_, None) => WebPkiClientVerifier::no_client_auth(),
+        (client_auth, Some(ca_file)) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(ca_file)? {
+                roots.add(cert).map_err(invalid)?;
+            }
+            let builder = WebPkiClientVerifier::builder(Arc::new(roots));
+            match client_auth {
+                ClientAuth::Required => builder.build(),
+                _ => builder.allow_unauthenticated().build(),
+            }
+            .map_err(invalid)?
+        }
+    };
+
+    let mut server_config = ServerConfig::builder()
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(read_certs(&config.cert_file)?, read_key(&config.key_file)?)
+        .map_err(invalid)?;
+
+    server_config.alpn_protocols = if http2 {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    } else {
+        vec![b"http/1.1".to_vec()]
+    };
+
+    Ok(server_config)
+}
+
+// Terminates TLS and records the client certificate principal on every
+// request of the connection
+#[derive(Clone)]
+pub struct TlsAcceptor {
+    inner: RustlsAcceptor<DefaultAcceptor>,
+}
+
+impl TlsAcceptor {
+    pub fn new(config: RustlsConfig) -> Self {
+        Self {
+            inner: RustlsAcceptor::new(config),
+        }
+    }
+}
+
+impl<I, S> Accept<I, S> for TlsAcceptor
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    S: Send + 'static,
+{
+    type Stream = TlsStream<I>;
+    type Service = AddExtension<S, TlsPeer>;
+    type Future = Pin<Box<dyn Future<Output = io::Result<(Self::Stream, Self::Service)>> + Send>>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            let (stream, service) = inner.accept(stream, service).await?;
+            let principal = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .and_then(|cert| ClientPrincipal::from_der(cert.as_ref()));
+
+            Ok((stream, AddExtension::new(service, TlsPeer { principal })))
+        })
+    }
+}
+
+fn modified_times(config: &TlsConfig) -> Vec<Option<SystemTime>> {
+    std::iter::once(&config.cert_file)
+        .chain(std::iter::once(&config.key_file))
+        .chain(config.client_ca_file.iter())
+        .map(|path| std::fs::metadata(Path::new(path)).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+// Reloads the certificate, key and client CA when any of the files change.
+// A bad file is logged and the current certificate kept.
+pub fn spawn_reloader(config: TlsConfig, http2: bool, rustls: RustlsConfig, shutdown: ShutdownHandle) {
+    let signal = shutdown.clone();
+    shutdown.spawn(async move {
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        let mut last_seen = modified_times(&config);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = signal.signalled() => return,
+            }
+
+            let modified = modified_times(&config);
+            if modified == last_seen {
+                continue;
+            }
+            last_seen = modified;
+
+            match server_config(&config, http2) {
+                Ok(server_config) => {
+                    rustls.reload_from_config(Arc::new(server_config));
+                    tracing::info!("TLS certificate reloaded");
+                }
+                Err(e) => tracing::error!("Failed to reload TLS certificate, keeping the current one: {}", e),
+            }
+        }
+    });
+} 
\ No newline at end of file
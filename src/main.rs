@@ -1,32 +1,63 @@
-use axum::{
-    routing::{get, post},
-    Router,
-    middleware,
-};
-use std::net::SocketAddr;
+use std::sync::Arc;
 mod infrastructure;
 mod application;
 mod domain;
 mod presentation;
+mod schema;
 
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
+
+    // One-off commands (e.g. bulk import/export) run instead of the server
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(infrastructure::cli::run(&args).await);
+    }
     
     // Load configuration
-    let config = infrastructure::config::app::AppConfig::from_env();
+    let config = match infrastructure::config::app::AppConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprint!("{}", e);
+            std::process::exit(1);
+        }
+    };
     
     // Setup database connection pool
-    let pool = infrastructure::config::database::establish_connection_pool();
+    let pool = infrastructure::config::database::establish_connection_pool(&config.database);
+    let db_router = Arc::new(infrastructure::db::replica::DbRouter::new(
+        pool.clone(),
+        infrastructure::config::database::establish_replica_pools(&config.database),
+    ));
     
     // Setup JWT service
-    let jwt_service = infrastructure::auth::jwt::JwtService::new();
+    let jwt_service = infrastructure::auth::jwt::JwtService::new(&config.auth);
+
+    let passwords = match config.auth.password_policy() {
+        Ok(policy) => domain::services::password::PasswordService::new(Arc::new(policy)),
+        Err(e) => {
+            eprint!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mailer = match infrastructure::mail::Mailer::new(&config.mail) {
+        Ok(mailer) => mailer,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
     
     // Create and run server
     let server = infrastructure::server::Server::new(
         config,
         pool,
+        db_router,
         jwt_service,
+        passwords,
+        mailer,
     );
     
     server.run().await;